@@ -1,3 +1,4 @@
+use crate::range::LoxRange;
 use crate::token::{DataType, Token};
 use std::hash::{Hash, Hasher};
 use std::{any::Any, rc::Rc};
@@ -19,6 +20,8 @@ impl HashedExpr {
             hash = stringify(&var.name);
         } else if let Some(ass) = expr.as_any().downcast_ref::<Assign>() {
             hash = stringify(&ass.name);
+        } else if let Some(this) = expr.as_any().downcast_ref::<This>() {
+            hash = stringify(&this.keyword);
         }
         HashedExpr { expr, hash }
     }
@@ -58,14 +61,26 @@ pub trait ExprVisitor {
     fn visit_assign_expr(&mut self, expr: &Assign) -> VisitorTypes;
     fn visit_binary_expr(&mut self, expr: &Binary) -> VisitorTypes;
     fn visit_call_expr(&mut self, expr: &Call) -> VisitorTypes;
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> VisitorTypes;
+    fn visit_destructure_expr(&mut self, expr: &Destructure) -> VisitorTypes;
     fn visit_get_expr(&mut self, expr: &Get) -> VisitorTypes;
     fn visit_grouping_expr(&mut self, expr: &Grouping) -> VisitorTypes;
+    fn visit_index_expr(&mut self, expr: &Index) -> VisitorTypes;
+    fn visit_interpolation_expr(&mut self, expr: &Interpolation) -> VisitorTypes;
+    fn visit_list_expr(&mut self, expr: &List) -> VisitorTypes;
     fn visit_literal_expr(&mut self, expr: &Literal) -> VisitorTypes;
     fn visit_logical_expr(&mut self, expr: &Logical) -> VisitorTypes;
+    fn visit_map_expr(&mut self, expr: &Map) -> VisitorTypes;
+    fn visit_optional_chain_expr(&mut self, expr: &OptionalChain) -> VisitorTypes;
+    fn visit_range_expr(&mut self, expr: &Range) -> VisitorTypes;
     fn visit_set_expr(&mut self, expr: &Set) -> VisitorTypes;
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> VisitorTypes;
+    fn visit_slice_expr(&mut self, expr: &Slice) -> VisitorTypes;
+    fn visit_spread_expr(&mut self, expr: &Spread) -> VisitorTypes;
     fn visit_super_expr(&mut self, expr: &Super) -> VisitorTypes;
     fn visit_this_expr(&mut self, expr: &This) -> VisitorTypes;
     fn visit_unary_expr(&mut self, expr: &Unary) -> VisitorTypes;
+    fn visit_update_expr(&mut self, expr: &Update) -> VisitorTypes;
     fn visit_variable_expr(&mut self, expr: &Variable) -> VisitorTypes;
 }
 
@@ -116,13 +131,21 @@ pub struct Call {
     pub callee: Rc<dyn Expr>,
     pub paren: Token,
     pub arguments: Vec<Rc<dyn Expr>>,
+    // Arguments given by parameter name: f(b: 2), they always come after the positional ones.
+    pub named: Vec<(Token, Rc<dyn Expr>)>,
 }
 impl Call {
-    pub fn new(callee: Rc<dyn Expr>, paren: Token, arguments: Vec<Rc<dyn Expr>>) -> Self {
+    pub fn new(
+        callee: Rc<dyn Expr>,
+        paren: Token,
+        arguments: Vec<Rc<dyn Expr>>,
+        named: Vec<(Token, Rc<dyn Expr>)>,
+    ) -> Self {
         Self {
             callee,
             paren,
             arguments,
+            named,
         }
     }
 }
@@ -136,13 +159,74 @@ impl Expr for Call {
     }
 }
 
+/// condition ? then_branch : else_branch
+pub struct Conditional {
+    pub condition: Rc<dyn Expr>,
+    pub then_branch: Rc<dyn Expr>,
+    pub else_branch: Rc<dyn Expr>,
+}
+impl Conditional {
+    pub fn new(
+        condition: Rc<dyn Expr>,
+        then_branch: Rc<dyn Expr>,
+        else_branch: Rc<dyn Expr>,
+    ) -> Self {
+        Self {
+            condition,
+            then_branch,
+            else_branch,
+        }
+    }
+}
+impl Expr for Conditional {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_conditional_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// [a, b] = value assigns the elements of a list to each target, the targets can be variables, fields or elements.
+/// The value is evaluated before anything is assigned, so [a, b] = [b, a] swaps a and b.
+pub struct Destructure {
+    pub targets: Vec<Rc<dyn Expr>>,
+    pub equals: Token,
+    pub value: Rc<dyn Expr>,
+}
+impl Destructure {
+    pub fn new(targets: Vec<Rc<dyn Expr>>, equals: Token, value: Rc<dyn Expr>) -> Self {
+        Self {
+            targets,
+            equals,
+            value,
+        }
+    }
+}
+impl Expr for Destructure {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_destructure_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Get {
     pub object: Rc<dyn Expr>,
     pub name: Token,
+    // Accessed with ?. instead of a dot, a nil object gives nil instead of an error.
+    pub optional: bool,
 }
 impl Get {
-    pub fn new(object: Rc<dyn Expr>, name: Token) -> Self {
-        Self { object, name }
+    pub fn new(object: Rc<dyn Expr>, name: Token, optional: bool) -> Self {
+        Self {
+            object,
+            name,
+            optional,
+        }
     }
 }
 impl Expr for Get {
@@ -173,6 +257,68 @@ impl Expr for Grouping {
     }
 }
 
+pub struct Index {
+    pub object: Rc<dyn Expr>,
+    pub bracket: Token,
+    pub index: Rc<dyn Expr>,
+}
+impl Index {
+    pub fn new(object: Rc<dyn Expr>, bracket: Token, index: Rc<dyn Expr>) -> Self {
+        Self {
+            object,
+            bracket,
+            index,
+        }
+    }
+}
+impl Expr for Index {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_index_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A string with expressions in it: "Hello ${name}".
+/// The parts are the string literals and the expressions in order.
+pub struct Interpolation {
+    pub parts: Vec<Rc<dyn Expr>>,
+}
+impl Interpolation {
+    pub fn new(parts: Vec<Rc<dyn Expr>>) -> Self {
+        Self { parts }
+    }
+}
+impl Expr for Interpolation {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_interpolation_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct List {
+    pub elements: Vec<Rc<dyn Expr>>,
+}
+impl List {
+    pub fn new(elements: Vec<Rc<dyn Expr>>) -> Self {
+        Self { elements }
+    }
+}
+impl Expr for List {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_list_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Literal {
     pub value: Option<DataType>,
 }
@@ -215,6 +361,71 @@ impl Expr for Logical {
     }
 }
 
+/// A map literal: {"name": value, ...}. The brace is kept for errors about the keys.
+pub struct Map {
+    pub brace: Token,
+    pub entries: Vec<(Rc<dyn Expr>, Rc<dyn Expr>)>,
+}
+impl Map {
+    pub fn new(brace: Token, entries: Vec<(Rc<dyn Expr>, Rc<dyn Expr>)>) -> Self {
+        Self { brace, entries }
+    }
+}
+impl Expr for Map {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_map_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A chain of calls, property accesses and indexes with a ?. in it: a?.b.c(d)[0].
+/// Once a ?. finds nil the rest of the chain is skipped and the whole chain gives nil.
+pub struct OptionalChain {
+    pub expression: Rc<dyn Expr>,
+}
+impl OptionalChain {
+    pub fn new(expression: Rc<dyn Expr>) -> Self {
+        Self { expression }
+    }
+}
+impl Expr for OptionalChain {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_optional_chain_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// start..end or start..=end, the operator tells which one it is.
+pub struct Range {
+    pub start: Rc<dyn Expr>,
+    pub operator: Token,
+    pub end: Rc<dyn Expr>,
+}
+impl Range {
+    pub fn new(start: Rc<dyn Expr>, operator: Token, end: Rc<dyn Expr>) -> Self {
+        Self {
+            start,
+            operator,
+            end,
+        }
+    }
+}
+impl Expr for Range {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_range_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Set {
     pub object: Rc<dyn Expr>,
     pub name: Token,
@@ -239,6 +450,89 @@ impl Expr for Set {
     }
 }
 
+pub struct SetIndex {
+    pub object: Rc<dyn Expr>,
+    pub bracket: Token,
+    pub index: Rc<dyn Expr>,
+    pub value: Rc<dyn Expr>,
+}
+impl SetIndex {
+    pub fn new(
+        object: Rc<dyn Expr>,
+        bracket: Token,
+        index: Rc<dyn Expr>,
+        value: Rc<dyn Expr>,
+    ) -> Self {
+        Self {
+            object,
+            bracket,
+            index,
+            value,
+        }
+    }
+}
+impl Expr for SetIndex {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_set_index_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Takes a part of a list or string: list[1:3], both bounds are optional.
+pub struct Slice {
+    pub object: Rc<dyn Expr>,
+    pub bracket: Token,
+    pub start: Option<Rc<dyn Expr>>,
+    pub end: Option<Rc<dyn Expr>>,
+}
+impl Slice {
+    pub fn new(
+        object: Rc<dyn Expr>,
+        bracket: Token,
+        start: Option<Rc<dyn Expr>>,
+        end: Option<Rc<dyn Expr>>,
+    ) -> Self {
+        Self {
+            object,
+            bracket,
+            start,
+            end,
+        }
+    }
+}
+impl Expr for Slice {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_slice_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A list spread into the arguments of a call: f(...xs). The parser only makes it inside an argument list.
+pub struct Spread {
+    pub operator: Token,
+    pub expr: Rc<dyn Expr>,
+}
+impl Spread {
+    pub fn new(operator: Token, expr: Rc<dyn Expr>) -> Self {
+        Self { operator, expr }
+    }
+}
+impl Expr for Spread {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_spread_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Super {
     pub keyword: Token,
     pub method: Token,
@@ -295,6 +589,41 @@ impl Expr for Unary {
     }
 }
 
+/// Updates a variable, field or list element in place: x += 1, obj.count++ or --list[i].
+/// The target is a Variable, Get or Index expression, its object and index are evaluated only once.
+pub struct Update {
+    pub target: Rc<dyn Expr>,
+    pub operator: Token,
+    // The right hand side of a compound assignment, ++ and -- don't have one.
+    pub value: Option<Rc<dyn Expr>>,
+    // A prefix update gives the new value, a postfix update the old one.
+    pub prefix: bool,
+}
+impl Update {
+    pub fn new(
+        target: Rc<dyn Expr>,
+        operator: Token,
+        value: Option<Rc<dyn Expr>>,
+        prefix: bool,
+    ) -> Self {
+        Self {
+            target,
+            operator,
+            value,
+            prefix,
+        }
+    }
+}
+impl Expr for Update {
+    fn accept(&self, visitor: &mut dyn ExprVisitor) -> VisitorTypes {
+        visitor.visit_update_expr(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Variable {
     pub name: Token,
 }
@@ -320,15 +649,52 @@ pub trait Stmt {
 }
 
 pub trait StmtVisitor {
+    fn visit_assert_stmt(&mut self, stmt: &Assert) -> VisitorTypes;
     fn visit_block_stmt(&mut self, stmt: &Block) -> VisitorTypes;
     fn visit_class_stmt(&mut self, stmt: &Class) -> VisitorTypes;
+    fn visit_defer_stmt(&mut self, stmt: &Defer) -> VisitorTypes;
     fn visit_expression_stmt(&mut self, stmt: &Expression) -> VisitorTypes;
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> VisitorTypes;
     fn visit_function_stmt(&mut self, stmt: &Function) -> VisitorTypes;
     fn visit_if_stmt(&mut self, stmt: &If) -> VisitorTypes;
+    fn visit_match_stmt(&mut self, stmt: &Match) -> VisitorTypes;
     fn visit_print_stmt(&mut self, stmt: &Print) -> VisitorTypes;
     fn visit_return_stmt(&mut self, stmt: &Return) -> VisitorTypes;
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> VisitorTypes;
     fn visit_var_stmt(&mut self, stmt: &Var) -> VisitorTypes;
     fn visit_while_stmt(&mut self, stmt: &While) -> VisitorTypes;
+    fn visit_yield_stmt(&mut self, stmt: &Yield) -> VisitorTypes;
+}
+/// assert condition, message; is a runtime error when the condition is false.
+/// The text is the source of the condition, the error shows it.
+pub struct Assert {
+    pub keyword: Token,
+    pub condition: Rc<dyn Expr>,
+    pub message: Option<Rc<dyn Expr>>,
+    pub text: String,
+}
+impl Assert {
+    pub fn new(
+        keyword: Token,
+        condition: Rc<dyn Expr>,
+        message: Option<Rc<dyn Expr>>,
+        text: String,
+    ) -> Self {
+        Self {
+            keyword,
+            condition,
+            message,
+            text,
+        }
+    }
+}
+impl Stmt for Assert {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_assert_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 pub struct Block {
     pub statements: Rc<Vec<Rc<dyn Stmt>>>,
@@ -350,16 +716,34 @@ impl Stmt for Block {
 // TODO super classes will be added later, commenting out super class code for now.
 pub struct Class {
     pub name: Token,
+    pub traits: Vec<Rc<dyn Expr>>,
     // Check if these works, cause they might not...
     pub methods: Vec<Rc<dyn Stmt>>,
+    pub getters: Vec<Rc<dyn Stmt>>,
+    pub setters: Vec<Rc<dyn Stmt>>,
+    pub static_methods: Vec<Rc<dyn Stmt>>,
+    pub static_fields: Vec<Rc<dyn Stmt>>,
     // pub super_class: Option<Rc<Variable>>,
 }
 impl Class {
     // super_class: Option<Rc<Variable>>
-    pub fn new(name: Token, methods: Vec<Rc<dyn Stmt>>) -> Self {
+    pub fn new(
+        name: Token,
+        traits: Vec<Rc<dyn Expr>>,
+        methods: Vec<Rc<dyn Stmt>>,
+        getters: Vec<Rc<dyn Stmt>>,
+        setters: Vec<Rc<dyn Stmt>>,
+        static_methods: Vec<Rc<dyn Stmt>>,
+        static_fields: Vec<Rc<dyn Stmt>>,
+    ) -> Self {
         Self {
             name,
+            traits,
             methods,
+            getters,
+            setters,
+            static_methods,
+            static_fields,
             // super_class,
         }
     }
@@ -373,6 +757,26 @@ impl Stmt for Class {
     }
 }
 
+/// defer statement; runs the statement when the block it is in is done, also when the block returns early.
+/// The deferred statements of a block run in the reverse order of their defers.
+pub struct Defer {
+    pub keyword: Token,
+    pub stmt: Rc<dyn Stmt>,
+}
+impl Defer {
+    pub fn new(keyword: Token, stmt: Rc<dyn Stmt>) -> Self {
+        Self { keyword, stmt }
+    }
+}
+impl Stmt for Defer {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_defer_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub struct Expression {
     pub expression: Rc<dyn Expr>,
 }
@@ -390,20 +794,73 @@ impl Stmt for Expression {
     }
 }
 
+/// for (name in iterable) body
+/// The keyword is the 'in' token, errors about the iterable are reported there.
+pub struct ForIn {
+    pub name: Token,
+    pub keyword: Token,
+    pub iterable: Rc<dyn Expr>,
+    pub body: Rc<dyn Stmt>,
+}
+impl ForIn {
+    pub fn new(name: Token, keyword: Token, iterable: Rc<dyn Expr>, body: Rc<dyn Stmt>) -> Self {
+        Self {
+            name,
+            keyword,
+            iterable,
+            body,
+        }
+    }
+}
+impl Stmt for ForIn {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_for_in_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A generator function (fun* name) gives back a generator when it's called, its body runs when next() is called.
 pub struct Function {
     pub name: Token,
-    pub params: Rc<Vec<Token>>,
+    pub params: Rc<Vec<Parameter>>,
     pub body: Rc<Vec<Rc<dyn Stmt>>>,
+    pub generator: bool,
 }
 impl Function {
-    pub fn new(name: Token, param: Rc<Vec<Token>>, body: Rc<Vec<Rc<dyn Stmt>>>) -> Self {
+    pub fn new(
+        name: Token,
+        param: Rc<Vec<Parameter>>,
+        body: Rc<Vec<Rc<dyn Stmt>>>,
+        generator: bool,
+    ) -> Self {
         Self {
             name,
             params: param,
             body,
+            generator,
         }
     }
 }
+/// A parameter of a function: b = 10. The default value is evaluated on every call that leaves the argument out,
+/// in the environment of the call, so it can use the parameters before it.
+/// A rest parameter (...args) is always the last one, it collects the extra arguments into a list.
+pub struct Parameter {
+    pub name: Token,
+    pub default: Option<Rc<dyn Expr>>,
+    pub rest: bool,
+}
+impl Parameter {
+    pub fn new(name: Token, default: Option<Rc<dyn Expr>>, rest: bool) -> Self {
+        Self {
+            name,
+            default,
+            rest,
+        }
+    }
+}
+
 impl Stmt for Function {
     fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
         visitor.visit_function_stmt(self)
@@ -440,6 +897,83 @@ impl Stmt for If {
     }
 }
 
+/// match (value) { 1, 2 => ...; x if x > 10 => ...; Point(p) => ...; _ => ... }
+/// Only the first arm that matches runs, a value that matches no arm does nothing.
+pub struct Match {
+    pub value: Rc<dyn Expr>,
+    pub arms: Vec<MatchArm>,
+}
+impl Match {
+    pub fn new(value: Rc<dyn Expr>, arms: Vec<MatchArm>) -> Self {
+        Self { value, arms }
+    }
+}
+impl Stmt for Match {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_match_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An arm matches when one of its patterns does and its guard, if it has one, is true.
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Rc<dyn Expr>>,
+    pub body: Rc<dyn Stmt>,
+}
+impl MatchArm {
+    pub fn new(patterns: Vec<Pattern>, guard: Option<Rc<dyn Expr>>, body: Rc<dyn Stmt>) -> Self {
+        Self {
+            patterns,
+            guard,
+            body,
+        }
+    }
+}
+
+pub enum Pattern {
+    // Matches a value equal to the literal: 1, "x", nil.
+    Literal {
+        token: Token,
+        value: DataType,
+    },
+    // Matches the whole numbers the range contains: 1..10.
+    Range {
+        token: Token,
+        range: LoxRange,
+    },
+    // Matches anything and binds it to the name.
+    Binding(Token),
+    // _ matches anything without binding it.
+    Wildcard(Token),
+    // Matches an instance of the class, optionally binding it: Point(p).
+    Class {
+        name: Token,
+        class: Rc<dyn Expr>,
+        binding: Option<Token>,
+    },
+}
+impl Pattern {
+    pub fn token(&self) -> &Token {
+        match self {
+            Pattern::Literal { token, .. } | Pattern::Range { token, .. } => token,
+            Pattern::Binding(token) | Pattern::Wildcard(token) => token,
+            Pattern::Class { name, .. } => name,
+        }
+    }
+
+    /// The name the matched value is bound to.
+    pub fn binding(&self) -> Option<&Token> {
+        match self {
+            Pattern::Binding(name) => Some(name),
+            Pattern::Class { binding, .. } => binding.as_ref(),
+            _ => None,
+        }
+    }
+}
+
 pub struct Print {
     pub expression: Rc<dyn Expr>,
 }
@@ -476,13 +1010,63 @@ impl Stmt for Return {
     }
 }
 
-pub struct Var {
+pub struct Trait {
     pub name: Token,
+    pub methods: Vec<Rc<dyn Stmt>>,
+}
+impl Trait {
+    pub fn new(name: Token, methods: Vec<Rc<dyn Stmt>>) -> Self {
+        Self { name, methods }
+    }
+}
+impl Stmt for Trait {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_trait_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A var or const declaration, the names of a const declaration can't be assigned to after it.
+pub struct Var {
+    pub pattern: BindingPattern,
     pub initializer: Option<Rc<dyn Expr>>,
+    pub constant: bool,
 }
 impl Var {
-    pub fn new(name: Token, initializer: Option<Rc<dyn Expr>>) -> Self {
-        Self { name, initializer }
+    pub fn new(pattern: BindingPattern, initializer: Option<Rc<dyn Expr>>, constant: bool) -> Self {
+        Self {
+            pattern,
+            initializer,
+            constant,
+        }
+    }
+}
+/// What a var declaration binds: a single name, the elements of a list: var [a, b] = pair;
+/// or the fields of a map or an instance: var {x, y} = point;
+pub enum BindingPattern {
+    Name(Token),
+    List { bracket: Token, names: Vec<Token> },
+    Map { brace: Token, names: Vec<Token> },
+}
+impl BindingPattern {
+    pub fn token(&self) -> &Token {
+        match self {
+            BindingPattern::Name(name) => name,
+            BindingPattern::List { bracket, .. } => bracket,
+            BindingPattern::Map { brace, .. } => brace,
+        }
+    }
+
+    /// Every name the pattern binds, in order.
+    pub fn names(&self) -> Vec<&Token> {
+        match self {
+            BindingPattern::Name(name) => vec![name],
+            BindingPattern::List { names, .. } | BindingPattern::Map { names, .. } => {
+                names.iter().collect()
+            }
+        }
     }
 }
 impl Stmt for Var {
@@ -511,3 +1095,22 @@ impl Stmt for While {
         self
     }
 }
+
+/// yield value; hands a value to the caller of a generator and suspends the generator until the next call of next().
+pub struct Yield {
+    pub keyword: Token,
+    pub value: Option<Rc<dyn Expr>>,
+}
+impl Yield {
+    pub fn new(keyword: Token, value: Option<Rc<dyn Expr>>) -> Self {
+        Self { keyword, value }
+    }
+}
+impl Stmt for Yield {
+    fn accept(&self, visitor: &mut dyn StmtVisitor) -> VisitorTypes {
+        visitor.visit_yield_stmt(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -53,11 +53,32 @@ impl ExprVisitor for AstPrinter {
         for arg in &expr.arguments {
             expressions.push(arg.as_ref());
         }
+        for (_, arg) in &expr.named {
+            expressions.push(arg.as_ref());
+        }
         self.parenthesize(&expr.paren.lexeme, expressions)
     }
 
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> VisitorTypes {
+        let expressions = vec![
+            expr.condition.as_ref(),
+            expr.then_branch.as_ref(),
+            expr.else_branch.as_ref(),
+        ];
+        self.parenthesize("?:", expressions)
+    }
+
+    fn visit_destructure_expr(&mut self, expr: &Destructure) -> VisitorTypes {
+        let mut expressions: Vec<&dyn Expr> = expr.targets.iter().map(|t| t.as_ref()).collect();
+        expressions.push(expr.value.as_ref());
+        self.parenthesize("destructure", expressions)
+    }
+
     fn visit_get_expr(&mut self, expr: &Get) -> VisitorTypes {
         let expressions = vec![expr.object.as_ref()];
+        if expr.optional {
+            return self.parenthesize(&format!("?.{}", expr.name.lexeme), expressions);
+        }
         self.parenthesize(&expr.name.lexeme, expressions)
     }
 
@@ -66,19 +87,43 @@ impl ExprVisitor for AstPrinter {
         self.parenthesize("group", expressions)
     }
 
+    fn visit_index_expr(&mut self, expr: &Index) -> VisitorTypes {
+        let expressions = vec![expr.object.as_ref(), expr.index.as_ref()];
+        self.parenthesize("index", expressions)
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Interpolation) -> VisitorTypes {
+        let expressions = expr.parts.iter().map(|e| e.as_ref()).collect();
+        self.parenthesize("interpolation", expressions)
+    }
+
+    fn visit_list_expr(&mut self, expr: &List) -> VisitorTypes {
+        let expressions = expr.elements.iter().map(|e| e.as_ref()).collect();
+        self.parenthesize("list", expressions)
+    }
+
     fn visit_literal_expr(&mut self, expr: &Literal) -> VisitorTypes {
         if expr.value.is_none() {
             VisitorTypes::String("nil".to_owned())
         } else {
             match expr.value.as_ref().unwrap() {
                 DataType::Number(n) => VisitorTypes::String(n.to_string()),
+                DataType::Integer(n) => VisitorTypes::String(n.to_string()),
+                DataType::BigInt(n) => VisitorTypes::String(format!("{n}n")),
+                DataType::Decimal(n) => VisitorTypes::String(format!("{n}d")),
                 DataType::String(s) => VisitorTypes::String(s.to_string()),
                 DataType::Bool(_) => VisitorTypes::String("bool".to_string()),
                 DataType::Nil => VisitorTypes::String("Nil".to_string()),
                 DataType::Function(_) => VisitorTypes::String("Function".to_string()),
                 DataType::Native(_) => VisitorTypes::String("Native".to_string()),
                 DataType::Class(_) => VisitorTypes::String("Class".to_string()),
+                DataType::Trait(_) => VisitorTypes::String("Trait".to_string()),
                 DataType::Instance(_) => VisitorTypes::String("Instance".to_string()),
+                DataType::List(_) => VisitorTypes::String("List".to_string()),
+                DataType::Map(_) => VisitorTypes::String("Map".to_string()),
+                DataType::Range(r) => VisitorTypes::String(r.to_string()),
+                DataType::Generator(_) => VisitorTypes::String("Generator".to_string()),
+                DataType::Fiber(_) => VisitorTypes::String("Fiber".to_string()),
             }
         }
     }
@@ -88,11 +133,54 @@ impl ExprVisitor for AstPrinter {
         self.parenthesize(&expr.operator.lexeme, expressions)
     }
 
+    fn visit_map_expr(&mut self, expr: &Map) -> VisitorTypes {
+        let expressions = expr
+            .entries
+            .iter()
+            .flat_map(|(key, value)| [key.as_ref(), value.as_ref()])
+            .collect();
+        self.parenthesize("map", expressions)
+    }
+
+    // The ?. links in the chain already show that it is optional.
+    fn visit_optional_chain_expr(&mut self, expr: &OptionalChain) -> VisitorTypes {
+        expr.expression.accept(self)
+    }
+
+    fn visit_range_expr(&mut self, expr: &Range) -> VisitorTypes {
+        let expressions = vec![expr.start.as_ref(), expr.end.as_ref()];
+        self.parenthesize(&expr.operator.lexeme, expressions)
+    }
+
     fn visit_set_expr(&mut self, expr: &Set) -> VisitorTypes {
         let expressions = vec![expr.object.as_ref(), expr.value.as_ref()];
         self.parenthesize(&expr.name.lexeme, expressions)
     }
 
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> VisitorTypes {
+        let expressions = vec![
+            expr.object.as_ref(),
+            expr.index.as_ref(),
+            expr.value.as_ref(),
+        ];
+        self.parenthesize("set-index", expressions)
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Slice) -> VisitorTypes {
+        // A missing bound is printed as nil.
+        let missing = Literal::new(None);
+        let expressions = vec![
+            expr.object.as_ref(),
+            expr.start.as_deref().unwrap_or(&missing),
+            expr.end.as_deref().unwrap_or(&missing),
+        ];
+        self.parenthesize("slice", expressions)
+    }
+
+    fn visit_spread_expr(&mut self, expr: &Spread) -> VisitorTypes {
+        self.parenthesize("...", vec![expr.expr.as_ref()])
+    }
+
     fn visit_super_expr(&mut self, _expr: &Super) -> VisitorTypes {
         VisitorTypes::String("super".to_owned())
     }
@@ -106,6 +194,17 @@ impl ExprVisitor for AstPrinter {
         self.parenthesize(&expr.operator.lexeme, expressions)
     }
 
+    fn visit_update_expr(&mut self, expr: &Update) -> VisitorTypes {
+        let mut expressions = vec![expr.target.as_ref()];
+        if let Some(value) = &expr.value {
+            expressions.push(value.as_ref());
+        }
+        if expr.prefix {
+            return self.parenthesize(&expr.operator.lexeme, expressions);
+        }
+        self.parenthesize(&format!("postfix{}", expr.operator.lexeme), expressions)
+    }
+
     fn visit_variable_expr(&mut self, expr: &Variable) -> VisitorTypes {
         VisitorTypes::String(expr.name.lexeme.clone())
     }
@@ -0,0 +1,481 @@
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+};
+
+/// Every limb holds 9 decimal digits, this keeps parsing and printing simple.
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+/// The amount of digits after the decimal point a division keeps when it doesn't end.
+pub const DIVISION_SCALE: u32 = 20;
+
+/// The most digits the result of a power can have, a larger power would take too long to compute.
+const MAX_POW_DIGITS: f64 = 100_000.0;
+
+/// An integer without a size limit, written as 123n in Lox.
+/// The magnitude is stored as little endian limbs without leading zero limbs, zero has no limbs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, limbs: Vec<u32>) -> BigInt {
+        let mut limbs = limbs;
+        trim(&mut limbs);
+        let negative = negative && !limbs.is_empty();
+        BigInt { negative, limbs }
+    }
+
+    /// Parses an optionally negative string of decimal digits.
+    pub fn parse(text: &str) -> Option<BigInt> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut limbs = Vec::new();
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(BASE_DIGITS);
+            limbs.push(digits[start..end].parse::<u32>().ok()?);
+            end = start;
+        }
+        Some(BigInt::from_parts(negative, limbs))
+    }
+
+    /// Converts a float without a fractional part, the conversion is exact.
+    pub fn from_f64(n: f64) -> Option<BigInt> {
+        if !n.is_finite() || n.fract() != 0.0 {
+            return None;
+        }
+        BigInt::parse(&format!("{n:.0}"))
+    }
+
+    /// Converts to the nearest float, very large values become infinity.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    pub fn to_u32(&self) -> Option<u32> {
+        match (self.negative, self.limbs.as_slice()) {
+            (_, []) => Some(0),
+            (false, [low]) => Some(*low),
+            (false, [low, high]) => u32::try_from(*high as u64 * BASE + *low as u64).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn neg(&self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.limbs.clone())
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.limbs.clone())
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitudes(&self.limbs, &other.limbs));
+        }
+        match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, sub_magnitudes(&other.limbs, &self.limbs))
+            }
+            _ => BigInt::from_parts(self.negative, sub_magnitudes(&self.limbs, &other.limbs)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitudes(&self.limbs, &other.limbs),
+        )
+    }
+
+    /// Division rounding towards zero, the remainder has the sign of the dividend.
+    /// Gives None when dividing by zero.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = divide_magnitudes(&self.limbs, &other.limbs);
+        Some((
+            BigInt::from_parts(self.negative != other.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        ))
+    }
+
+    /// Division rounding towards negative infinity, the remainder has the sign of the divisor.
+    /// Gives None when dividing by zero.
+    pub fn div_mod_floor(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        let (quotient, remainder) = self.div_rem(other)?;
+        if !remainder.is_zero() && remainder.negative != other.negative {
+            let one = BigInt::from(1);
+            Some((quotient.sub(&one), remainder.add(other)))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    /// Gives None when the result would have more than MAX_POW_DIGITS digits.
+    pub fn pow(&self, exponent: u32) -> Option<BigInt> {
+        // 0, 1 and -1 keep their size whatever the exponent is.
+        if let Some(&high) = self.limbs.last() {
+            let digits = (self.limbs.len() - 1) as f64 * BASE_DIGITS as f64 + (high as f64).log10();
+            if digits * exponent as f64 > MAX_POW_DIGITS {
+                return None;
+            }
+        }
+        Some(self.power(exponent))
+    }
+
+    pub fn pow10(exponent: u32) -> BigInt {
+        BigInt::from(10).power(exponent)
+    }
+
+    fn power(&self, exponent: u32) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent >>= 1;
+        }
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> BigInt {
+        let mut magnitude = n.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+        BigInt::from_parts(n < 0, limbs)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitudes(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        let most_significant = match limbs.next() {
+            Some(limb) => limb,
+            None => return write!(f, "0"),
+        };
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{most_significant}")?;
+        for limb in limbs {
+            write!(f, "{limb:09}")?;
+        }
+        Ok(())
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// Subtracts b from a, a must not be smaller than b.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, limb) in a.iter().enumerate() {
+        let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += BASE as i64;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(&mut result);
+    result
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u64; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let product = result[i + j] + *x as u64 * *y as u64 + carry;
+            result[i + j] = product % BASE;
+            carry = product / BASE;
+        }
+        result[i + b.len()] += carry;
+    }
+    let mut result: Vec<u32> = result.into_iter().map(|limb| limb as u32).collect();
+    trim(&mut result);
+    result
+}
+
+fn mul_small(a: &[u32], n: u32) -> Vec<u32> {
+    mul_magnitudes(a, &[n])
+}
+
+/// Schoolbook long division, every limb of the quotient is found with a binary search.
+fn divide_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = Vec::with_capacity(a.len());
+    let mut remainder: Vec<u32> = Vec::new();
+    for limb in a.iter().rev() {
+        remainder.insert(0, *limb);
+        trim(&mut remainder);
+        let (mut low, mut high) = (0, BASE as u32 - 1);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if compare_magnitudes(&mul_small(b, middle), &remainder) == Ordering::Greater {
+                high = middle - 1;
+            } else {
+                low = middle;
+            }
+        }
+        remainder = sub_magnitudes(&remainder, &mul_small(b, low));
+        quotient.push(low);
+    }
+    quotient.reverse();
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+fn trim(limbs: &mut Vec<u32>) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+/// An exact decimal number, written as 1.10d in Lox.
+/// The value is unscaled / 10^scale, the scale is kept so 1.10d prints as 1.10.
+#[derive(Clone, Debug)]
+pub struct Decimal {
+    unscaled: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Parses an optionally negative decimal like 12, -0.5 or 1.10.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (whole, fraction) = match text.split_once('.') {
+            Some((whole, fraction)) if !fraction.is_empty() => (whole, fraction),
+            Some(_) => return None,
+            None => (text, ""),
+        };
+        let unscaled = BigInt::parse(&format!("{whole}{fraction}"))?;
+        Some(Decimal {
+            unscaled,
+            scale: fraction.len() as u32,
+        })
+    }
+
+    /// Converts a float through its shortest representation, so 0.1 becomes 0.1d.
+    pub fn from_f64(n: f64) -> Option<Decimal> {
+        if !n.is_finite() {
+            return None;
+        }
+        Decimal::parse(&format!("{n}"))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    /// Gives the value as a big integer if there's nothing after the decimal point.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        let (quotient, remainder) = self.unscaled.div_rem(&BigInt::pow10(self.scale))?;
+        if remainder.is_zero() {
+            Some(quotient)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.unscaled.is_zero()
+    }
+
+    fn rescale(&self, scale: u32) -> BigInt {
+        self.unscaled.mul(&BigInt::pow10(scale - self.scale))
+    }
+
+    /// Both unscaled values at the largest scale of the two.
+    fn aligned(&self, other: &Decimal) -> (BigInt, BigInt, u32) {
+        let scale = self.scale.max(other.scale);
+        (self.rescale(scale), other.rescale(scale), scale)
+    }
+
+    pub fn neg(&self) -> Decimal {
+        Decimal {
+            unscaled: self.unscaled.neg(),
+            scale: self.scale,
+        }
+    }
+
+    pub fn add(&self, other: &Decimal) -> Decimal {
+        let (l, r, scale) = self.aligned(other);
+        Decimal {
+            unscaled: l.add(&r),
+            scale,
+        }
+    }
+
+    pub fn sub(&self, other: &Decimal) -> Decimal {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Decimal) -> Decimal {
+        Decimal {
+            unscaled: self.unscaled.mul(&other.unscaled),
+            scale: self.scale + other.scale,
+        }
+    }
+
+    /// Divides with DIVISION_SCALE digits after the decimal point, the rest is cut off.
+    /// Trailing zeros are dropped again, down to the scale of the operands: 1.00d / 4d is 0.25.
+    /// Gives None when dividing by zero.
+    pub fn div(&self, other: &Decimal) -> Option<Decimal> {
+        let operand_scale = self.scale.max(other.scale);
+        let scale = operand_scale.max(DIVISION_SCALE);
+        let dividend = self.rescale(scale + other.scale);
+        let (mut unscaled, _) = dividend.div_rem(&other.unscaled)?;
+        let mut scale = scale;
+        let ten = BigInt::from(10);
+        while scale > operand_scale {
+            let (quotient, remainder) = unscaled.div_rem(&ten)?;
+            if !remainder.is_zero() {
+                break;
+            }
+            unscaled = quotient;
+            scale -= 1;
+        }
+        Some(Decimal { unscaled, scale })
+    }
+
+    /// Division rounding towards negative infinity, the quotient is a whole number.
+    /// Gives None when dividing by zero.
+    pub fn div_mod_floor(&self, other: &Decimal) -> Option<(Decimal, Decimal)> {
+        let (l, r, scale) = self.aligned(other);
+        let (quotient, remainder) = l.div_mod_floor(&r)?;
+        Some((
+            Decimal::from(quotient),
+            Decimal {
+                unscaled: remainder,
+                scale,
+            },
+        ))
+    }
+
+    /// Gives None when the result would have more than MAX_POW_DIGITS digits, before or after the decimal point.
+    pub fn pow(&self, exponent: u32) -> Option<Decimal> {
+        let scale = self
+            .scale
+            .checked_mul(exponent)
+            .filter(|&scale| scale as f64 <= MAX_POW_DIGITS)?;
+        Some(Decimal {
+            unscaled: self.unscaled.pow(exponent)?,
+            scale,
+        })
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(n: BigInt) -> Decimal {
+        Decimal {
+            unscaled: n,
+            scale: 0,
+        }
+    }
+}
+
+/// Decimals are compared by value, 1.10d is equal to 1.1d.
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let (l, r, _) = self.aligned(other);
+        l.cmp(&r)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.unscaled.is_negative() {
+            write!(f, "-")?;
+        }
+        let digits = self.unscaled.abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{whole}.{fraction}")
+    }
+}
@@ -11,32 +11,135 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct LoxClass {
     name: String,
+    // A class value is held by every instance and by the values the interpreter passes around,
+    // the tables of its members are shared so it stays small and cheap to copy.
+    members: Rc<Members>,
+    // Class-level variables are shared by every copy of the class value.
+    fields: Rc<RefCell<HashMap<String, DataType>>>,
+}
+
+#[derive(Debug)]
+struct Members {
     methods: HashMap<String, LoxFunction>,
+    getters: HashMap<String, LoxFunction>,
+    setters: HashMap<String, LoxFunction>,
+    static_methods: HashMap<String, LoxFunction>,
 }
 impl LoxClass {
-    pub fn new(name: String, methods: HashMap<String, LoxFunction>) -> LoxClass {
-        LoxClass { name, methods }
+    pub fn new(
+        name: String,
+        methods: HashMap<String, LoxFunction>,
+        getters: HashMap<String, LoxFunction>,
+        setters: HashMap<String, LoxFunction>,
+        static_methods: HashMap<String, LoxFunction>,
+        fields: HashMap<String, DataType>,
+    ) -> LoxClass {
+        let members = Members {
+            methods,
+            getters,
+            setters,
+            static_methods,
+        };
+        LoxClass {
+            name,
+            members: Rc::new(members),
+            fields: Rc::new(RefCell::new(fields)),
+        }
+    }
+
+    /// Looks up a class-level variable or a static method, static methods are never bound to an instance.
+    pub fn get(&self, token: &Token) -> VisitorTypes {
+        if let Some(value) = self.fields.borrow().get(&token.lexeme) {
+            return VisitorTypes::DataType(Some(value.clone()));
+        }
+        if let Some(method) = self.members.static_methods.get(&token.lexeme) {
+            return VisitorTypes::DataType(Some(DataType::Function(method.clone())));
+        }
+
+        VisitorTypes::RunTimeError {
+            token: Some(token.dup()),
+            msg: format!("Undefined static property '{}'.", token.lexeme),
+        }
+    }
+
+    pub fn set(&self, token: &Token, value: Option<DataType>) {
+        self.fields
+            .borrow_mut()
+            .insert(token.dup().lexeme, value.unwrap_or(DataType::Nil));
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Names of the public class-level variables, sorted so reflection output is stable.
+    pub fn field_names(&self) -> Vec<String> {
+        public_sorted(self.fields.borrow().keys())
+    }
+
+    /// Names of the public instance methods, sorted so reflection output is stable.
+    pub fn method_names(&self) -> Vec<String> {
+        public_sorted(self.members.methods.keys())
+    }
+
+    /// Two class values are the same class when they share their class-level variables.
+    pub fn is_same(&self, other: &LoxClass) -> bool {
+        Rc::ptr_eq(&self.fields, &other.fields)
     }
 }
 
 impl LoxCallable for LoxClass {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> DataType {
-        let instance = Rc::new(LoxInstance::new(self.clone()));
-        if let Some(initializer) = self.methods.get("init") {
-            initializer
-                .bind(instance.clone())
-                .call(interpreter, arguments);
-        }
-        DataType::Instance(instance)
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        self.call_with_defaults(interpreter, arguments.into_iter().map(Some).collect())
     }
 
     fn arity(&self) -> usize {
-        if let Some(initializer) = self.methods.get("init") {
+        if let Some(initializer) = self.members.methods.get("init") {
             initializer.arity()
         } else {
             0
         }
     }
+
+    fn min_arity(&self) -> usize {
+        if let Some(initializer) = self.members.methods.get("init") {
+            initializer.min_arity()
+        } else {
+            0
+        }
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.members
+            .methods
+            .get("init")
+            .is_some_and(|initializer| initializer.is_variadic())
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        if let Some(initializer) = self.members.methods.get("init") {
+            initializer.parameter_names()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn call_with_defaults(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<DataType>>,
+    ) -> VisitorTypes {
+        let instance = Rc::new(LoxInstance::new(self.clone()));
+        if let Some(initializer) = self.members.methods.get("init") {
+            if let VisitorTypes::RunTimeError { token, msg } = initializer
+                .bind(instance.clone())
+                .call_with_defaults(interpreter, arguments)
+            {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+        }
+        VisitorTypes::DataType(Some(DataType::Instance(instance)))
+    }
 }
 
 impl fmt::Display for LoxClass {
@@ -45,6 +148,24 @@ impl fmt::Display for LoxClass {
     }
 }
 
+/// A trait is a named set of methods that classes can include with the 'with' keyword.
+#[derive(Debug, Clone)]
+pub struct LoxTrait {
+    pub name: String,
+    pub methods: HashMap<String, LoxFunction>,
+}
+impl LoxTrait {
+    pub fn new(name: String, methods: HashMap<String, LoxFunction>) -> LoxTrait {
+        LoxTrait { name, methods }
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Trait {}>", self.name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LoxInstance {
     class: LoxClass,
@@ -58,17 +179,35 @@ impl LoxInstance {
             fields: RefCell::new(HashMap::new()),
         }
     }
-    pub fn get(&self, token: &Token) -> VisitorTypes {
+    /// Looks up a field, a getter or a method, in that order.
+    /// Getters run right away with 'this' bound, methods are returned bound to the instance.
+    /// Private members can only be read when the instance is accessed through 'this'.
+    pub fn get(
+        self: &Rc<Self>,
+        token: &Token,
+        through_this: bool,
+        interpreter: &mut Interpreter,
+    ) -> VisitorTypes {
+        if is_private(&token.lexeme) && !through_this {
+            return private_access_error(token);
+        }
         if self.fields.borrow().contains_key(&token.lexeme) {
             return VisitorTypes::DataType(Some(
                 self.fields.borrow().get(&token.lexeme).unwrap().clone(),
             ));
         }
-        if self.class.methods.contains_key(&token.lexeme) {
-            let method = self.class.methods.get(&token.lexeme).unwrap().clone();
-            return VisitorTypes::DataType(Some(DataType::Function(
-                method.bind(Rc::new(self.clone())),
-            )));
+        if let Some(getter) = self.class.members.getters.get(&token.lexeme) {
+            return getter.bind(Rc::clone(self)).call(interpreter, Vec::new());
+        }
+        if self.class.members.methods.contains_key(&token.lexeme) {
+            let method = self
+                .class
+                .members
+                .methods
+                .get(&token.lexeme)
+                .unwrap()
+                .clone();
+            return VisitorTypes::DataType(Some(DataType::Function(method.bind(Rc::clone(self)))));
         }
 
         VisitorTypes::RunTimeError {
@@ -77,10 +216,76 @@ impl LoxInstance {
         }
     }
 
-    pub fn set(&self, token: &Token, value: Option<DataType>) {
-        self.fields
-            .borrow_mut()
-            .insert(token.dup().lexeme, value.unwrap_or(DataType::Nil));
+    /// Runs the setter with 'this' bound if the class defines one, otherwise the field is stored on the instance.
+    /// Private members can only be written when the instance is accessed through 'this'.
+    pub fn set(
+        self: &Rc<Self>,
+        token: &Token,
+        value: Option<DataType>,
+        through_this: bool,
+        interpreter: &mut Interpreter,
+    ) -> VisitorTypes {
+        if is_private(&token.lexeme) && !through_this {
+            return private_access_error(token);
+        }
+        let value = value.unwrap_or(DataType::Nil);
+        if let Some(setter) = self.class.members.setters.get(&token.lexeme) {
+            setter.bind(Rc::clone(self)).call(interpreter, vec![value]);
+        } else {
+            self.fields.borrow_mut().insert(token.dup().lexeme, value);
+        }
+        VisitorTypes::Void(())
+    }
+
+    /// Looks up a method of the class and binds it to the instance, fields and getters are skipped.
+    pub fn bound_method(self: &Rc<Self>, name: &str) -> Option<LoxFunction> {
+        self.class
+            .members
+            .methods
+            .get(name)
+            .map(|method| method.bind(Rc::clone(self)))
+    }
+
+    /// Whether reading the property gives a field, a getter or a method instead of an error.
+    pub fn has_property(&self, name: &str) -> bool {
+        self.fields.borrow().contains_key(name)
+            || self.class.members.getters.contains_key(name)
+            || self.class.members.methods.contains_key(name)
+    }
+
+    pub fn class(&self) -> &LoxClass {
+        &self.class
+    }
+
+    /// Names of the public fields stored on the instance, sorted so reflection output is stable.
+    pub fn field_names(&self) -> Vec<String> {
+        public_sorted(self.fields.borrow().keys())
+    }
+
+    /// Private fields are only seen from inside the class, like in field_names.
+    pub fn has_field(&self, name: &str) -> bool {
+        !is_private(name) && self.fields.borrow().contains_key(name)
+    }
+}
+
+fn public_sorted<'a>(names: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut names: Vec<String> = names.filter(|n| !is_private(n)).cloned().collect();
+    names.sort();
+    names
+}
+
+/// Private members are prefixed with a #.
+pub fn is_private(name: &str) -> bool {
+    name.starts_with('#')
+}
+
+fn private_access_error(token: &Token) -> VisitorTypes {
+    VisitorTypes::RunTimeError {
+        token: Some(token.dup()),
+        msg: format!(
+            "Can't access private member '{}' from outside its class.",
+            token.lexeme
+        ),
     }
 }
 
@@ -0,0 +1,44 @@
+/// What happens when integer arithmetic doesn't fit in an i64.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntOverflow {
+    /// The result becomes a float, precision is lost but the script keeps running.
+    Promote,
+    /// The operation is a runtime error.
+    Error,
+}
+
+static mut INT_OVERFLOW: IntOverflow = IntOverflow::Promote;
+
+pub fn set_int_overflow(int_overflow: IntOverflow) {
+    unsafe {
+        INT_OVERFLOW = int_overflow;
+    }
+}
+
+pub fn get_int_overflow() -> IntOverflow {
+    unsafe { INT_OVERFLOW }
+}
+
+/// Whether assert statements check their condition, --no-assert turns them into no-ops.
+static mut ASSERTIONS: bool = true;
+
+pub fn set_assertions(assertions: bool) {
+    unsafe {
+        ASSERTIONS = assertions;
+    }
+}
+
+pub fn get_assertions() -> bool {
+    unsafe { ASSERTIONS }
+}
+
+/// Applies a command line flag, returns false if the flag is not known.
+pub fn apply_flag(flag: &str) -> bool {
+    match flag {
+        "--int-overflow=promote" => set_int_overflow(IntOverflow::Promote),
+        "--int-overflow=error" => set_int_overflow(IntOverflow::Error),
+        "--no-assert" => set_assertions(false),
+        _ => return false,
+    }
+    true
+}
@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     rc::Rc,
 };
 
@@ -13,6 +13,8 @@ use crate::{
 pub struct Environment {
     pub enclosing: Option<Rc<RefCell<Environment>>>,
     pub values: HashMap<String, DataType>,
+    // Names declared with const, the resolver already rejects assignments to local constants.
+    constants: HashSet<String>,
 }
 
 impl Environment {
@@ -20,6 +22,7 @@ impl Environment {
         Self {
             values: HashMap::new(),
             enclosing: None,
+            constants: HashSet::new(),
         }
     }
 
@@ -27,6 +30,7 @@ impl Environment {
         Self {
             enclosing: Some(enclosing),
             values: HashMap::new(),
+            constants: HashSet::new(),
         }
     }
 
@@ -34,6 +38,16 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    pub fn define_constant(&mut self, name: String, value: DataType) {
+        self.values.insert(name.clone(), value);
+        self.constants.insert(name);
+    }
+
+    /// Only looks at this environment, a constant can be shadowed in an inner scope.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
     pub fn get(&self, name: &Token) -> VisitorTypes {
         if let Some(object) = self.values.get(&name.dup().lexeme) {
             VisitorTypes::DataType(Some(object.clone()))
@@ -60,6 +74,12 @@ impl Environment {
     }
 
     pub fn assign(&mut self, name: &Token, value: DataType) -> VisitorTypes {
+        if self.constants.contains(&name.lexeme) {
+            return VisitorTypes::RunTimeError {
+                token: Some(name.dup()),
+                msg: format!("Can't assign to constant '{}'.", name.lexeme),
+            };
+        }
         if let Entry::Occupied(mut object) = self.values.entry(name.dup().lexeme) {
             object.insert(value);
             return VisitorTypes::Void(());
@@ -0,0 +1,302 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::{self, Debug, Display, Formatter},
+    ptr,
+    rc::Rc,
+};
+
+use corosensei::{
+    stack::{DefaultStack, Stack},
+    Coroutine, CoroutineResult, Yielder,
+};
+
+use crate::{
+    ast::VisitorTypes, error::runtime_error, function::LoxCallable, interpreter::Interpreter,
+    token::DataType,
+};
+
+/// A fiber gets as much stack as a thread Rust spawns. The stack is only backed by memory as far as it's used,
+/// but every one is a mapping of its own and a process can only have so many of them.
+const STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// Linux allows a process 65530 mappings by default and every stack takes two, one for the guard page below it.
+/// Running out of them makes every allocation fail, so making a fiber past this many is a runtime error instead.
+const MAX_STACKS: usize = 30_000;
+
+thread_local! {
+    // The stacks of the fibers that are alive, a stack is freed when its fiber is dropped.
+    static STACKS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A call in a fiber is a "Stack overflow." runtime error once less than this is left on its stack.
+/// Everything the interpreter runs until the next call fits in it, so a fiber never runs into the guard page
+/// below its stack, which would end the process.
+const STACK_RESERVE: usize = 256 * 1024;
+
+/// A fiber is resumed with the value given to resume().
+type FiberYielder = Yielder<DataType, Suspend>;
+
+/// Why a fiber stopped before its function was done.
+enum Suspend {
+    Yield(Box<DataType>),
+    Sleep(u64),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    /// Not started yet or stopped at a Fiber.yield, resume() runs it.
+    Suspended,
+    Running,
+    /// Waiting in the scheduler, only the scheduler runs it.
+    Sleeping,
+    Done,
+}
+
+/// A fiber runs a function that can stop anywhere with Fiber.yield(v), even in a function it called.
+/// resume() runs the fiber up to its next yield and gives the yielded value, the value given to the next resume()
+/// is what Fiber.yield gives back.
+///
+/// A generator takes its body apart to stop in the middle of it, a fiber can't because a yield can be in any
+/// function it calls. The interpreter runs a fiber on a stack of its own instead, and switches stacks to stop it.
+///
+/// The stack of a stopped fiber still holds the interpreter its function runs on, so every fiber gets an interpreter
+/// of its own that shares the globals and the scheduler with the one that made it. The code that resumes a fiber
+/// never uses the interpreter of the fiber, and the fiber never uses the interpreter of the code that resumes it.
+///
+/// Switching stacks needs assembly for every platform, corosensei does that for us. It maps the stack with a guard
+/// page below it, and unwinds the stack of a fiber that is dropped before its function is done, so the values on it
+/// are dropped as well.
+#[derive(Clone)]
+pub struct LoxFiber {
+    state: Rc<State>,
+}
+
+struct State {
+    coroutine: RefCell<Coroutine<DataType, Suspend, VisitorTypes, DefaultStack>>,
+    // The yielder is on the stack of the fiber, it is set when the fiber starts.
+    yielder: Rc<Cell<*const FiberYielder>>,
+    status: Cell<Status>,
+}
+
+impl LoxFiber {
+    /// The function runs on an interpreter of its own, it gets the value of the first resume() when it has a parameter.
+    /// Making a fiber is a runtime error when there is no memory left for its stack.
+    pub fn new(
+        function: Rc<dyn LoxCallable>,
+        interpreter: &Interpreter,
+    ) -> Result<LoxFiber, Box<VisitorTypes>> {
+        let error = |msg: &str| {
+            Err(Box::new(VisitorTypes::RunTimeError {
+                token: None,
+                msg: msg.to_string(),
+            }))
+        };
+        if STACKS.with(Cell::get) >= MAX_STACKS {
+            let msg = format!("Can't have more than {} fibers at once.", MAX_STACKS);
+            return error(&msg);
+        }
+        let stack = match DefaultStack::new(STACK_SIZE) {
+            Ok(stack) => stack,
+            Err(_) => return error("Not enough memory for the stack of a fiber."),
+        };
+        STACKS.with(|stacks| stacks.set(stacks.get() + 1));
+        let yielder = Rc::new(Cell::new(ptr::null()));
+        let started = Rc::clone(&yielder);
+        let interpreter = interpreter.for_fiber(stack.limit().get() + STACK_RESERVE);
+        let coroutine = Coroutine::with_stack(
+            stack,
+            move |fiber_yielder: &FiberYielder, value: DataType| {
+                started.set(fiber_yielder);
+                let mut interpreter = interpreter;
+                let arguments = if function.arity() == 0 {
+                    Vec::new()
+                } else {
+                    vec![Some(value)]
+                };
+                function.call_with_defaults(&mut interpreter, arguments)
+            },
+        );
+        let state = State {
+            coroutine: RefCell::new(coroutine),
+            yielder,
+            status: Cell::new(Status::Suspended),
+        };
+        Ok(LoxFiber {
+            state: Rc::new(state),
+        })
+    }
+
+    /// Runs the fiber until it yields, sleeps or its function is done. Gives the yielded or the returned value,
+    /// a fiber that sleeps gives nil and goes on when the scheduler wakes it.
+    /// A runtime error the function gives back ends the fiber and comes out of resume().
+    pub fn resume(
+        &self,
+        interpreter: &Interpreter,
+        value: DataType,
+    ) -> Result<DataType, Box<VisitorTypes>> {
+        let msg = match self.state.status.get() {
+            Status::Suspended => None,
+            Status::Running => Some("Can't resume a fiber that is running."),
+            Status::Sleeping => Some("Can't resume a sleeping fiber, the scheduler wakes it."),
+            Status::Done => Some("Can't resume a finished fiber."),
+        };
+        if let Some(msg) = msg {
+            return Err(Box::new(VisitorTypes::RunTimeError {
+                token: None,
+                msg: msg.to_string(),
+            }));
+        }
+        self.state.status.set(Status::Running);
+        interpreter
+            .scheduler
+            .borrow_mut()
+            .running
+            .push(self.clone());
+        let result = self.state.coroutine.borrow_mut().resume(value);
+        interpreter.scheduler.borrow_mut().running.pop();
+        match result {
+            CoroutineResult::Yield(Suspend::Yield(value)) => {
+                self.state.status.set(Status::Suspended);
+                Ok(*value)
+            }
+            CoroutineResult::Yield(Suspend::Sleep(ms)) => {
+                interpreter
+                    .scheduler
+                    .borrow_mut()
+                    .wake_after(self.clone(), ms);
+                Ok(DataType::Nil)
+            }
+            CoroutineResult::Return(result) => {
+                self.state.status.set(Status::Done);
+                match result {
+                    VisitorTypes::DataType(d) => Ok(d.unwrap_or(DataType::Nil)),
+                    error => Err(Box::new(error)),
+                }
+            }
+        }
+    }
+
+    /// Stops the running fiber, resume() gives the value. Gives the value of the resume() that goes on with it.
+    pub fn yield_value(&self, value: DataType) -> DataType {
+        self.suspend(Suspend::Yield(Box::new(value)))
+    }
+
+    /// Stops the running fiber and hands it to the scheduler, which wakes it after the milliseconds.
+    pub fn sleep(&self, ms: u64) {
+        self.suspend(Suspend::Sleep(ms));
+    }
+
+    /// Only the running fiber can be suspended, Fiber.yield and sleep() get it from the scheduler.
+    fn suspend(&self, suspend: Suspend) -> DataType {
+        // SAFETY: the yielder is on the stack of the fiber, the coroutine sets the pointer to it when it starts and
+        // the yielder stays there until the function returns. Only a running fiber is suspended, so it has started
+        // and its function hasn't returned, and the state that owns the stack is still alive since self holds it.
+        let yielder = unsafe { &*self.state.yielder.get() };
+        yielder.suspend(suspend)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.status.get() == Status::Done
+    }
+
+    /// Two fiber values are the same fiber when they share their state.
+    pub fn is_same(&self, other: &LoxFiber) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/// Runs the fibers handed to it by Fiber.spawn and sleep(), in the order of the time they wake up at.
+/// The time is made up and sleep() doesn't wait, so the fibers take turns the same way on every run.
+#[derive(Default)]
+pub struct Scheduler {
+    // The fibers that are running, the innermost one is on top.
+    running: Vec<LoxFiber>,
+    // Milliseconds since the program started.
+    time: u64,
+    // Fibers that wake up at the same time run in the order they went to sleep in.
+    sleeping: BTreeMap<(u64, u64), LoxFiber>,
+    slept: u64,
+}
+
+impl Scheduler {
+    /// The fiber that is running right now, None outside of fibers.
+    pub fn current(&self) -> Option<LoxFiber> {
+        self.running.last().cloned()
+    }
+
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Hands a fiber to the scheduler, it runs once the running code sleeps or is done.
+    pub fn spawn(&mut self, fiber: LoxFiber) {
+        self.wake_after(fiber, 0);
+    }
+
+    fn wake_after(&mut self, fiber: LoxFiber, ms: u64) {
+        fiber.state.status.set(Status::Sleeping);
+        self.sleeping
+            .insert((self.time.saturating_add(ms), self.slept), fiber);
+        self.slept += 1;
+    }
+
+    /// Takes out the first fiber to wake up, if it wakes up before the given time.
+    fn wake(&mut self, until: Option<u64>) -> Option<LoxFiber> {
+        let (&(time, _), _) = self.sleeping.first_key_value()?;
+        if until.is_some_and(|until| time > until) {
+            return None;
+        }
+        let (_, fiber) = self.sleeping.pop_first()?;
+        self.time = time;
+        fiber.state.status.set(Status::Suspended);
+        Some(fiber)
+    }
+}
+
+/// Runs the sleeping fibers that wake up until the given time, or until none are left.
+/// A runtime error ends the fiber it came out of, the other fibers go on.
+pub fn run_scheduler(interpreter: &Interpreter, until: Option<u64>) {
+    loop {
+        // The scheduler can't stay borrowed while a fiber runs, the fiber uses it as well.
+        let fiber = match interpreter.scheduler.borrow_mut().wake(until) {
+            Some(fiber) => fiber,
+            None => break,
+        };
+        match fiber.resume(interpreter, DataType::Nil) {
+            // A fiber that yields lets the other fibers that are awake run before it goes on.
+            Ok(_) if fiber.state.status.get() == Status::Suspended => {
+                interpreter.scheduler.borrow_mut().spawn(fiber);
+            }
+            Ok(_) => {}
+            // An error with a token has been reported where it happened.
+            Err(error) => {
+                if let VisitorTypes::RunTimeError { token: None, msg } = *error {
+                    runtime_error(&None, &msg);
+                }
+            }
+        }
+    }
+    if let Some(until) = until {
+        let mut scheduler = interpreter.scheduler.borrow_mut();
+        scheduler.time = scheduler.time.max(until);
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        STACKS.with(|stacks| stacks.set(stacks.get() - 1));
+    }
+}
+
+impl Display for LoxFiber {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Fiber>")
+    }
+}
+
+impl Debug for LoxFiber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoxFiber").finish()
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
-    ast::{Function, Stmt, VisitorTypes},
+    ast::{Function, Parameter, Stmt, VisitorTypes},
     class::LoxInstance,
     environment::Environment,
+    generator::LoxGenerator,
     interpreter::Interpreter,
     token::{DataType, Token},
 };
@@ -12,17 +13,48 @@ use std::{
 };
 
 pub trait LoxCallable: Debug + Display {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> DataType;
+    /// Runtime errors without a token are reported at the closing paren of the call.
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes;
     fn arity(&self) -> usize;
+    /// The least amount of arguments a call needs, parameters with a default value can be left out.
+    fn min_arity(&self) -> usize {
+        self.arity()
+    }
+    /// A variadic callable takes any amount of arguments past its arity.
+    fn is_variadic(&self) -> bool {
+        false
+    }
+    /// Names that named arguments can use, in the order of the parameters.
+    fn parameter_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Properties of the callable itself, like Fiber.yield.
+    fn property(&self, _name: &str) -> Option<DataType> {
+        None
+    }
+    /// Calls with one slot per parameter, an empty slot takes the default value of its parameter.
+    /// The slots past the arity are the extra arguments of a variadic call.
+    fn call_with_defaults(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<DataType>>,
+    ) -> VisitorTypes {
+        let arguments = arguments
+            .into_iter()
+            .map(|a| a.unwrap_or(DataType::Nil))
+            .collect();
+        self.call(interpreter, arguments)
+    }
 }
 
 #[derive(Clone)]
 pub struct LoxFunction {
     pub body: Rc<Vec<Rc<dyn Stmt>>>,
-    pub params: Rc<Vec<Token>>,
+    pub params: Rc<Vec<Parameter>>,
     name: Box<Token>,
     closure: Rc<RefCell<Environment>>,
     is_init: bool,
+    is_generator: bool,
 }
 
 impl LoxFunction {
@@ -37,6 +69,7 @@ impl LoxFunction {
             name: Box::new(declaration.name.dup()),
             closure: Rc::clone(closure),
             is_init,
+            is_generator: declaration.generator,
         }
     }
 
@@ -50,22 +83,87 @@ impl LoxFunction {
             name: self.name.clone(),
             closure: Rc::new(env),
             is_init: self.is_init,
+            is_generator: self.is_generator,
         }
     }
 }
 
 impl LoxCallable for LoxFunction {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> DataType {
-        let mut environment = Environment::new_enclosing(Rc::clone(&self.closure));
-        for (i, token) in self.params.iter().enumerate() {
-            let value = match arguments.get(i) {
-                Some(d) => d.clone(),
-                None => DataType::Nil,
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        self.call_with_defaults(interpreter, arguments.into_iter().map(Some).collect())
+    }
+
+    /// The rest parameter isn't counted, it can be left out.
+    fn arity(&self) -> usize {
+        self.params.iter().filter(|p| !p.rest).count()
+    }
+
+    fn min_arity(&self) -> usize {
+        self.params
+            .iter()
+            .filter(|p| p.default.is_none() && !p.rest)
+            .count()
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.params.last().is_some_and(|p| p.rest)
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .filter(|p| !p.rest)
+            .map(|p| p.name.lexeme.clone())
+            .collect()
+    }
+
+    fn call_with_defaults(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<DataType>>,
+    ) -> VisitorTypes {
+        // Defaults are evaluated in the new environment so they can use the parameters before them.
+        let environment = Rc::new(RefCell::new(Environment::new_enclosing(Rc::clone(
+            &self.closure,
+        ))));
+        for (i, param) in self.params.iter().enumerate() {
+            if param.rest {
+                let rest = arguments
+                    .iter()
+                    .skip(i)
+                    .map(|a| a.clone().unwrap_or(DataType::Nil))
+                    .collect();
+                environment.borrow_mut().define(
+                    param.name.dup().lexeme,
+                    DataType::List(Rc::new(RefCell::new(rest))),
+                );
+                continue;
+            }
+            let value = match (arguments.get(i).cloned().flatten(), &param.default) {
+                (Some(d), _) => d,
+                (None, Some(default)) => {
+                    match interpreter.evaluate_in(default, Rc::clone(&environment)) {
+                        VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+                        VisitorTypes::RunTimeError { token, msg } => {
+                            return VisitorTypes::RunTimeError { token, msg };
+                        }
+                        _ => panic!("Interpreter entered impossible state."),
+                    }
+                }
+                (None, None) => DataType::Nil,
             };
-            environment.define(token.dup().lexeme, value);
+            environment
+                .borrow_mut()
+                .define(param.name.dup().lexeme, value);
+        }
+        // The body of a generator function doesn't run yet, it runs when the generator is asked for a value.
+        if self.is_generator {
+            let generator = LoxGenerator::new(&self.name.lexeme, &self.body, environment);
+            return VisitorTypes::DataType(Some(DataType::Generator(generator)));
         }
+        let environment = environment.borrow().clone();
         let statements = Rc::new(&self.body);
-        match interpreter.execute_block(&statements, environment) {
+        let value = match interpreter.execute_block(&statements, environment) {
             VisitorTypes::Return(Some(d)) => {
                 if self.is_init {
                     match self.closure.borrow().get_at(0, "this") {
@@ -77,11 +175,8 @@ impl LoxCallable for LoxFunction {
                 }
             }
             _ => DataType::Nil,
-        }
-    }
-
-    fn arity(&self) -> usize {
-        self.params.len()
+        };
+        VisitorTypes::DataType(Some(value))
     }
 }
 
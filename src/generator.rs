@@ -0,0 +1,340 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug, Display, Formatter},
+    mem,
+    rc::Rc,
+};
+
+use crate::{
+    ast::{Block, Defer, Expr, ForIn, If, Match, Stmt, VisitorTypes, While, Yield},
+    environment::Environment,
+    interpreter::{Interpreter, Iteration},
+    token::{DataType, Token},
+};
+
+/// Calling a generator function (fun*) gives a generator. Its body runs a piece at a time,
+/// every call of next() runs it up to the next yield and gives the yielded value.
+///
+/// The interpreter runs a statement by calling itself for the statements inside it, so it can't stop halfway.
+/// A generator keeps a stack of the blocks and loops it is in instead. A statement without a yield in it
+/// is run by the interpreter as a whole, a statement with a yield in it is taken apart here.
+#[derive(Clone)]
+pub struct LoxGenerator {
+    name: String,
+    state: Rc<RefCell<State>>,
+}
+
+struct State {
+    // The innermost block or loop is on top, the generator is done when there are no frames left.
+    frames: Vec<Frame>,
+    running: bool,
+}
+
+enum Frame {
+    /// Statements that run one after another, the deferred ones run when the frame is left.
+    Block {
+        statements: Rc<Vec<Rc<dyn Stmt>>>,
+        next: usize,
+        environment: Rc<RefCell<Environment>>,
+        deferred: Vec<Rc<dyn Stmt>>,
+    },
+    /// A while loop, the condition is checked before every run of the body.
+    While {
+        condition: Rc<dyn Expr>,
+        body: Rc<dyn Stmt>,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// A for-in loop, every value runs the body in a new environment that holds the loop variable.
+    ForIn {
+        name: Box<Token>,
+        keyword: Box<Token>,
+        body: Rc<dyn Stmt>,
+        iteration: Iteration,
+        environment: Rc<RefCell<Environment>>,
+    },
+}
+
+impl Frame {
+    fn block(statements: Rc<Vec<Rc<dyn Stmt>>>, environment: Rc<RefCell<Environment>>) -> Frame {
+        Frame::Block {
+            statements,
+            next: 0,
+            environment,
+            deferred: Vec::new(),
+        }
+    }
+
+    /// Runs the deferred statements of a block that is left.
+    fn leave(self, interpreter: &mut Interpreter) {
+        if let Frame::Block {
+            environment,
+            deferred,
+            ..
+        } = self
+        {
+            for stmt in deferred.iter().rev() {
+                interpreter.execute_in(stmt, Rc::clone(&environment));
+            }
+        }
+    }
+}
+
+/// What running a statement in a block leads to.
+enum Step {
+    Next,
+    Enter(Frame),
+    Defer(Rc<dyn Stmt>),
+    Yield(DataType),
+    Return,
+}
+
+impl LoxGenerator {
+    /// The environment holds the parameters of the call that made the generator.
+    pub fn new(
+        name: &str,
+        body: &Rc<Vec<Rc<dyn Stmt>>>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> LoxGenerator {
+        let state = State {
+            frames: vec![Frame::block(Rc::clone(body), environment)],
+            running: false,
+        };
+        LoxGenerator {
+            name: name.to_string(),
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Runs the body up to the next yield and gives the yielded value, or None when the body is done.
+    /// A statement with a runtime error in it is left and the body goes on after it, like it does in a normal function.
+    pub fn resume(
+        &self,
+        interpreter: &mut Interpreter,
+    ) -> Result<Option<DataType>, Box<VisitorTypes>> {
+        // The frames are taken out while the body runs, they are put back when it stops at a yield.
+        let mut frames = {
+            let mut state = self.state.borrow_mut();
+            if state.running {
+                return Err(Box::new(VisitorTypes::RunTimeError {
+                    token: None,
+                    msg: "Can't resume a generator that is already running.".to_string(),
+                }));
+            }
+            state.running = true;
+            mem::take(&mut state.frames)
+        };
+        let value = run(&mut frames, interpreter);
+        if value.is_some() {
+            self.state.borrow_mut().frames = frames;
+        }
+        self.state.borrow_mut().running = false;
+        Ok(value)
+    }
+
+    /// Whether the body is done, next() gives nil from then on.
+    pub fn is_done(&self) -> bool {
+        let state = self.state.borrow();
+        !state.running && state.frames.is_empty()
+    }
+
+    /// Two generator values are the same generator when they share their state.
+    pub fn is_same(&self, other: &LoxGenerator) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/// Runs the frames until a yield gives a value or there are no frames left.
+/// Frames are only left over when a yield gives a value, the blocks a return leaves run their defers.
+fn run(frames: &mut Vec<Frame>, interpreter: &mut Interpreter) -> Option<DataType> {
+    while let Some(frame) = frames.last_mut() {
+        let step = match frame {
+            Frame::Block {
+                statements,
+                next,
+                environment,
+                ..
+            } => match statements.get(*next) {
+                Some(stmt) => {
+                    let stmt = Rc::clone(stmt);
+                    *next += 1;
+                    // The error has been reported, the block goes on like it does in a normal function.
+                    run_statement(&stmt, Rc::clone(environment), interpreter).unwrap_or(Step::Next)
+                }
+                None => {
+                    if let Some(frame) = frames.pop() {
+                        frame.leave(interpreter);
+                    }
+                    continue;
+                }
+            },
+            Frame::While {
+                condition,
+                body,
+                environment,
+            } => match interpreter.condition_in(condition, Rc::clone(environment)) {
+                Ok(condition) if interpreter.is_truthy(&condition) => {
+                    let body = Rc::new(vec![Rc::clone(body)]);
+                    Step::Enter(Frame::block(body, Rc::clone(environment)))
+                }
+                // A condition without a value ends the loop like a false one, it has been reported.
+                _ => {
+                    frames.pop();
+                    continue;
+                }
+            },
+            Frame::ForIn {
+                name,
+                keyword,
+                body,
+                iteration,
+                environment,
+            } => match interpreter.next_iteration(iteration, keyword) {
+                Ok(Some(value)) => {
+                    let mut loop_environment = Environment::new_enclosing(Rc::clone(environment));
+                    loop_environment.define(name.dup().lexeme, value);
+                    let body = Rc::new(vec![Rc::clone(body)]);
+                    Step::Enter(Frame::block(body, Rc::new(RefCell::new(loop_environment))))
+                }
+                // An error of the iterator ends the loop, it has been reported.
+                Ok(None) | Err(_) => {
+                    frames.pop();
+                    continue;
+                }
+            },
+        };
+        match step {
+            Step::Next => {}
+            Step::Enter(frame) => frames.push(frame),
+            Step::Defer(stmt) => {
+                if let Some(Frame::Block { deferred, .. }) = frames.last_mut() {
+                    deferred.push(stmt);
+                }
+            }
+            Step::Yield(value) => return Some(value),
+            Step::Return => {
+                while let Some(frame) = frames.pop() {
+                    frame.leave(interpreter);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Runs a statement of a block. Blocks, ifs, loops and matches with a yield in them become frames,
+/// so the generator can stop in the middle of them. Gives the error of a statement that failed once it's reported.
+fn run_statement(
+    stmt: &Rc<dyn Stmt>,
+    environment: Rc<RefCell<Environment>>,
+    interpreter: &mut Interpreter,
+) -> Result<Step, Box<VisitorTypes>> {
+    if let Some(stmt) = stmt.as_any().downcast_ref::<Defer>() {
+        return Ok(Step::Defer(Rc::clone(&stmt.stmt)));
+    }
+    if let Some(stmt) = stmt.as_any().downcast_ref::<Yield>() {
+        // An error in the value is reported like one in the value of a return, nothing is yielded then.
+        let value = match &stmt.value {
+            Some(value) => match interpreter.evaluate_in(value, environment) {
+                VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return Err(Box::new(
+                        interpreter.visitor_runtime_error(token.as_ref(), &msg),
+                    ));
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            },
+            None => DataType::Nil,
+        };
+        return Ok(Step::Yield(value));
+    }
+    if !contains_yield(stmt) {
+        // Runtime errors are already reported, the block goes on like it does in a normal function.
+        return match interpreter.execute_in(stmt, environment) {
+            VisitorTypes::Return(_) => Ok(Step::Return),
+            _ => Ok(Step::Next),
+        };
+    }
+    if let Some(block) = stmt.as_any().downcast_ref::<Block>() {
+        let environment = Rc::new(RefCell::new(Environment::new_enclosing(environment)));
+        return Ok(Step::Enter(Frame::block(
+            Rc::clone(&block.statements),
+            environment,
+        )));
+    }
+    if let Some(stmt) = stmt.as_any().downcast_ref::<If>() {
+        let condition = interpreter.condition_in(&stmt.condition, Rc::clone(&environment))?;
+        let branch = if interpreter.is_truthy(&condition) {
+            Some(&stmt.then_branch)
+        } else {
+            stmt.else_branch.as_ref()
+        };
+        return Ok(match branch {
+            Some(branch) => {
+                Step::Enter(Frame::block(Rc::new(vec![Rc::clone(branch)]), environment))
+            }
+            None => Step::Next,
+        });
+    }
+    if let Some(stmt) = stmt.as_any().downcast_ref::<While>() {
+        return Ok(Step::Enter(Frame::While {
+            condition: Rc::clone(&stmt.condition),
+            body: Rc::clone(&stmt.body),
+            environment,
+        }));
+    }
+    if let Some(stmt) = stmt.as_any().downcast_ref::<ForIn>() {
+        let iterable = match interpreter.evaluate_in(&stmt.iterable, Rc::clone(&environment)) {
+            VisitorTypes::DataType(d) => d,
+            error => return Err(Box::new(interpreter.iteration_error(error, &stmt.keyword))),
+        };
+        let iteration = interpreter.iteration(iterable, &stmt.keyword)?;
+        return Ok(Step::Enter(Frame::ForIn {
+            name: Box::new(stmt.name.dup()),
+            keyword: Box::new(stmt.keyword.dup()),
+            body: Rc::clone(&stmt.body),
+            iteration,
+            environment,
+        }));
+    }
+    // contains_yield only looks for yields in blocks, ifs, loops and matches.
+    let stmt = stmt.as_any().downcast_ref::<Match>().unwrap();
+    Ok(match interpreter.select_arm(stmt, environment)? {
+        Some((body, environment)) => Step::Enter(Frame::block(Rc::new(vec![body]), environment)),
+        None => Step::Next,
+    })
+}
+
+/// Whether a yield of this generator is somewhere in the statement.
+/// Functions and classes declared in the body are left out, the resolver doesn't allow a yield in them.
+fn contains_yield(stmt: &Rc<dyn Stmt>) -> bool {
+    let stmt = stmt.as_any();
+    if stmt.is::<Yield>() {
+        true
+    } else if let Some(block) = stmt.downcast_ref::<Block>() {
+        block.statements.iter().any(contains_yield)
+    } else if let Some(stmt) = stmt.downcast_ref::<If>() {
+        contains_yield(&stmt.then_branch) || stmt.else_branch.as_ref().is_some_and(contains_yield)
+    } else if let Some(stmt) = stmt.downcast_ref::<While>() {
+        contains_yield(&stmt.body)
+    } else if let Some(stmt) = stmt.downcast_ref::<ForIn>() {
+        contains_yield(&stmt.body)
+    } else if let Some(stmt) = stmt.downcast_ref::<Match>() {
+        stmt.arms.iter().any(|arm| contains_yield(&arm.body))
+    } else {
+        false
+    }
+}
+
+impl Display for LoxGenerator {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Generator {}>", self.name)
+    }
+}
+
+impl Debug for LoxGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoxGenerator")
+            .field("name:", &self.name)
+            .finish()
+    }
+}
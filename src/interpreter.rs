@@ -1,6 +1,8 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
     io::{self, Error, ErrorKind},
     rc::Rc,
 };
@@ -10,19 +12,34 @@ use substring::Substring;
 
 use crate::{
     ast::*,
-    class::LoxClass,
+    bignum::{BigInt, Decimal},
+    class::{LoxClass, LoxInstance, LoxTrait},
+    config::{self, IntOverflow},
     environment::Environment,
     error,
+    fiber::{run_scheduler, Scheduler},
     function::{LoxCallable, LoxFunction, LoxNative},
-    native_functions::{Clock, Println},
-    token::{DataType, Token, TokenType},
+    generator::LoxGenerator,
+    native_functions::{
+        ClassName, ClassOf, Clock, FiberNew, FiberResume, Fields, GeneratorNext, GetField,
+        HasField, Len, Methods, Println, SetField, Sleep, StepBy, StringMethod, ToBigInt,
+        ToDecimal, ToNumber, TypeOf,
+    },
+    range::LoxRange,
+    token::{DataType, LoxList, LoxMap, Token, TokenType},
 };
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
-    locals: RefCell<HashMap<HashedExpr, usize>>,
+    // Shared with the interpreters of fibers, the resolver fills it in before the statements run.
+    locals: Rc<RefCell<HashMap<HashedExpr, usize>>>,
     environment: RefCell<Rc<RefCell<Environment>>>,
     is_repl: bool,
     is_last_statement: bool,
+    // Set when a ?. finds nil, the links after it in the optional chain give nil until the chain ends.
+    short_circuit: bool,
+    pub scheduler: Rc<RefCell<Scheduler>>,
+    // The lowest address the stack can grow to before a call is refused, only fibers have one.
+    stack_limit: Option<usize>,
 }
 impl Interpreter {
     pub fn new(is_repl: bool) -> Interpreter {
@@ -38,20 +55,71 @@ impl Interpreter {
         });
         globals.borrow_mut().define("println".to_string(), println);
 
+        let natives: Vec<(&str, Rc<dyn LoxCallable>)> = vec![
+            ("len", Rc::new(Len::new("Len".to_string()))),
+            ("typeof", Rc::new(TypeOf::new("TypeOf".to_string()))),
+            ("classOf", Rc::new(ClassOf::new("ClassOf".to_string()))),
+            (
+                "className",
+                Rc::new(ClassName::new("ClassName".to_string())),
+            ),
+            ("fields", Rc::new(Fields::new("Fields".to_string()))),
+            ("methods", Rc::new(Methods::new("Methods".to_string()))),
+            ("hasField", Rc::new(HasField::new("HasField".to_string()))),
+            ("getField", Rc::new(GetField::new("GetField".to_string()))),
+            ("setField", Rc::new(SetField::new("SetField".to_string()))),
+            ("bigint", Rc::new(ToBigInt::new("BigInt".to_string()))),
+            ("decimal", Rc::new(ToDecimal::new("Decimal".to_string()))),
+            ("toNumber", Rc::new(ToNumber::new("ToNumber".to_string()))),
+            ("Fiber", Rc::new(FiberNew::new("Fiber".to_string()))),
+            ("sleep", Rc::new(Sleep::new("Sleep".to_string()))),
+        ];
+        for (name, function) in natives {
+            let native = DataType::Native(LoxNative { function });
+            globals.borrow_mut().define(name.to_string(), native);
+        }
+
         Interpreter {
             globals: Rc::clone(&globals),
-            locals: RefCell::new(HashMap::new()),
+            locals: Rc::new(RefCell::new(HashMap::new())),
             environment: RefCell::new(Rc::clone(&globals)),
             is_repl,
             is_last_statement: false,
+            short_circuit: false,
+            scheduler: Rc::new(RefCell::new(Scheduler::default())),
+            stack_limit: None,
+        }
+    }
+
+    /// An interpreter for a fiber to run on, it shares the globals, the resolved variables and the scheduler.
+    /// A call that would use the stack of the fiber below the limit is a runtime error.
+    pub fn for_fiber(&self, stack_limit: usize) -> Interpreter {
+        Interpreter {
+            globals: Rc::clone(&self.globals),
+            locals: Rc::clone(&self.locals),
+            environment: RefCell::new(Rc::clone(&self.globals)),
+            is_repl: false,
+            is_last_statement: false,
+            short_circuit: false,
+            scheduler: Rc::clone(&self.scheduler),
+            stack_limit: Some(stack_limit),
         }
     }
 
     pub fn interpret(&mut self, statements: Vec<Rc<dyn Stmt>>) {
+        let mut deferred = Vec::new();
         for (i, stmt) in statements.iter().enumerate() {
             self.is_last_statement = i == statements.len() - 1;
+            // Top-level defers run when the script is done.
+            if let Some(defer) = stmt.as_any().downcast_ref::<Defer>() {
+                deferred.push(Rc::clone(&defer.stmt));
+                continue;
+            }
             self.execute(stmt);
         }
+        self.run_deferred(deferred);
+        // Fibers that are still sleeping run once the statements are done.
+        run_scheduler(self, None);
     }
     pub fn execute_block(
         &mut self,
@@ -59,14 +127,73 @@ impl Interpreter {
         environment: Environment,
     ) -> VisitorTypes {
         let previous = self.environment.replace(Rc::new(RefCell::new(environment)));
+        let mut deferred = Vec::new();
+        let mut result = VisitorTypes::Void(());
         for stmt in statements.iter() {
+            // A deferred statement runs in the environment of the block when the block is done.
+            if let Some(defer) = stmt.as_any().downcast_ref::<Defer>() {
+                deferred.push(Rc::clone(&defer.stmt));
+                continue;
+            }
             if let VisitorTypes::Return(r) = self.execute(&stmt.clone()) {
-                self.environment.replace(previous);
-                return VisitorTypes::Return(r);
+                result = VisitorTypes::Return(r);
+                break;
             }
         }
+        self.run_deferred(deferred);
         self.environment.replace(previous);
-        VisitorTypes::Void(())
+        result
+    }
+
+    /// Runs the deferred statements of a block, the last one deferred runs first.
+    /// A return value of the block is already evaluated, a deferred statement doesn't change it.
+    fn run_deferred(&mut self, deferred: Vec<Rc<dyn Stmt>>) {
+        for stmt in deferred.iter().rev() {
+            self.execute(stmt);
+        }
+    }
+
+    /// Evaluates an expression in another environment, like a default parameter value in the environment of its call.
+    pub fn evaluate_in(
+        &mut self,
+        expr: &Rc<dyn Expr>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> VisitorTypes {
+        let previous = self.environment.replace(environment);
+        let result = expr.accept(self);
+        self.environment.replace(previous);
+        result
+    }
+
+    /// Executes a statement in another environment, like the body of a match arm in the environment of its pattern.
+    pub fn execute_in(
+        &mut self,
+        stmt: &Rc<dyn Stmt>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> VisitorTypes {
+        let previous = self.environment.replace(environment);
+        let result = self.execute(stmt);
+        self.environment.replace(previous);
+        result
+    }
+
+    /// Evaluates the condition of an if or a while in an environment, one without a value is reported.
+    pub fn condition_in(
+        &mut self,
+        condition: &Rc<dyn Expr>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<DataType, Box<VisitorTypes>> {
+        match self.evaluate_in(condition, environment) {
+            VisitorTypes::DataType(Some(d)) => Ok(d),
+            _ => Err(Box::new(
+                self.visitor_runtime_error(None, "Expected a condition."),
+            )),
+        }
+    }
+
+    /// The environment statements are executed in right now.
+    fn environment(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.environment.borrow())
     }
 
     fn execute(&mut self, stmt: &Rc<dyn Stmt>) -> VisitorTypes {
@@ -84,6 +211,11 @@ impl Interpreter {
     }
 
     fn stringify_helper(&self, data_type: Option<DataType>) -> String {
+        self.stringify_nested(data_type, &mut Vec::new())
+    }
+
+    /// Seen holds the lists and maps that are being printed, one that contains itself is printed as [...] or {...}.
+    fn stringify_nested(&self, data_type: Option<DataType>, seen: &mut Vec<*const ()>) -> String {
         let result = match data_type {
             Some(DataType::String(s)) => s,
             Some(DataType::Number(n)) => {
@@ -93,18 +225,53 @@ impl Interpreter {
                 }
                 number
             }
+            Some(DataType::Integer(n)) => n.to_string(),
+            Some(DataType::BigInt(n)) => n.to_string(),
+            Some(DataType::Decimal(n)) => n.to_string(),
             Some(DataType::Bool(b)) => b.to_string(),
             Some(DataType::Nil) => "nil".to_string(),
             Some(DataType::Function(f)) => format!("{f}"),
             Some(DataType::Native(n)) => format!("{n}"),
             Some(DataType::Class(c)) => format!("{c}"),
+            Some(DataType::Trait(t)) => format!("{t}"),
             Some(DataType::Instance(i)) => format!("{i}"),
+            Some(DataType::List(l)) => {
+                let ptr = Rc::as_ptr(&l) as *const ();
+                if seen.contains(&ptr) {
+                    return "[...]".to_string();
+                }
+                seen.push(ptr);
+                let elements: Vec<String> = l
+                    .borrow()
+                    .iter()
+                    .map(|e| self.stringify_nested(Some(e.clone()), seen))
+                    .collect();
+                seen.pop();
+                format!("[{}]", elements.join(", "))
+            }
+            Some(DataType::Map(m)) => {
+                let ptr = Rc::as_ptr(&m) as *const ();
+                if seen.contains(&ptr) {
+                    return "{...}".to_string();
+                }
+                seen.push(ptr);
+                let entries: Vec<String> = m
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| format!("{k}: {}", self.stringify_nested(Some(v.clone()), seen)))
+                    .collect();
+                seen.pop();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Some(DataType::Range(r)) => r.to_string(),
+            Some(DataType::Generator(g)) => g.to_string(),
+            Some(DataType::Fiber(fiber)) => fiber.to_string(),
             None => "nil".to_string(),
         };
         result
     }
 
-    fn is_truthy(&self, data_type: &DataType) -> bool {
+    pub fn is_truthy(&self, data_type: &DataType) -> bool {
         match data_type {
             DataType::Bool(b) => *b,
             DataType::Nil => false,
@@ -114,11 +281,21 @@ impl Interpreter {
 
     fn is_equal(&self, a: &DataType, b: &DataType) -> bool {
         match (a, b) {
-            (DataType::Number(a), DataType::Number(b)) => a == b,
             (DataType::String(a), DataType::String(b)) => a == b,
             (DataType::Bool(a), DataType::Bool(b)) => a == b,
             (DataType::Nil, DataType::Nil) => true,
-            _ => false,
+            (DataType::List(a), DataType::List(b)) => Rc::ptr_eq(a, b),
+            (DataType::Map(a), DataType::Map(b)) => Rc::ptr_eq(a, b),
+            (DataType::Range(a), DataType::Range(b)) => a == b,
+            (DataType::Generator(a), DataType::Generator(b)) => a.is_same(b),
+            (DataType::Fiber(a), DataType::Fiber(b)) => a.is_same(b),
+            (DataType::Instance(a), DataType::Instance(b)) => Rc::ptr_eq(a, b),
+            (DataType::Class(a), DataType::Class(b)) => a.is_same(b),
+            // Numbers of different kinds are equal when their values are: 1 == 1.0, 1 == 1n and 1.10d == 1.1d.
+            (a, b) => match Operands::new(Some(a), Some(b)) {
+                Some(operands) => operands.compare() == Some(Ordering::Equal),
+                None => false,
+            },
         }
     }
 
@@ -139,14 +316,35 @@ impl Interpreter {
         }
     }
 
+    /// Assigns to a resolved local or a global variable, gives the assigned value.
+    /// Assigning to an undefined or a constant global is a runtime error.
+    fn assign_variable(&self, name: &Token, expr: &Rc<dyn Expr>, value: DataType) -> VisitorTypes {
+        let local = HashedExpr::new(expr.clone());
+        let result = if let Some(distance) = self.locals.borrow().get(&local) {
+            self.environment
+                .borrow()
+                .borrow_mut()
+                .assign_at(*distance, name, value.clone())
+        } else {
+            self.globals.borrow_mut().assign(name, value.clone())
+        };
+        match result {
+            VisitorTypes::RunTimeError { token, msg } => {
+                self.visitor_runtime_error(token.as_ref(), &msg)
+            }
+            _ => VisitorTypes::DataType(Some(value)),
+        }
+    }
+
     fn repl_printer(&self, expr: &VisitorTypes) {
         if let VisitorTypes::DataType(d) = expr {
-            let value = self.repl_stringify(d.clone());
+            let value = self.repl_stringify(d.clone(), &mut Vec::new());
             println!("{value}");
         }
     }
 
-    fn repl_stringify(&self, data_type: Option<DataType>) -> String {
+    /// Seen works like it does for stringify_nested.
+    fn repl_stringify(&self, data_type: Option<DataType>, seen: &mut Vec<*const ()>) -> String {
         let result = match data_type {
             Some(DataType::String(s)) => s.yellow().to_string(),
             Some(DataType::Number(n)) => {
@@ -156,6 +354,9 @@ impl Interpreter {
                 }
                 number.blue().to_string()
             }
+            Some(DataType::Integer(n)) => n.to_string().blue().to_string(),
+            Some(DataType::BigInt(n)) => n.to_string().blue().to_string(),
+            Some(DataType::Decimal(n)) => n.to_string().blue().to_string(),
             Some(DataType::Bool(b)) => {
                 let string = b.to_string().green();
                 string.to_string()
@@ -168,13 +369,48 @@ impl Interpreter {
                 .bright_red()
                 .to_string(),
             Some(DataType::Class(c)) => format!("{}", c).on_white().bright_purple().to_string(),
+            Some(DataType::Trait(t)) => format!("{}", t).on_white().bright_purple().to_string(),
             Some(DataType::Instance(i)) => format!("{}", i).on_white().bright_purple().to_string(),
+            Some(DataType::List(l)) => {
+                let ptr = Rc::as_ptr(&l) as *const ();
+                if seen.contains(&ptr) {
+                    return "[...]".to_string();
+                }
+                seen.push(ptr);
+                let elements: Vec<String> = l
+                    .borrow()
+                    .iter()
+                    .map(|e| self.repl_stringify(Some(e.clone()), seen))
+                    .collect();
+                seen.pop();
+                format!("[{}]", elements.join(", "))
+            }
+            Some(DataType::Map(m)) => {
+                let ptr = Rc::as_ptr(&m) as *const ();
+                if seen.contains(&ptr) {
+                    return "{...}".to_string();
+                }
+                seen.push(ptr);
+                let entries: Vec<String> = m
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = self.repl_stringify(Some(DataType::String(k.clone())), seen);
+                        format!("{key}: {}", self.repl_stringify(Some(v.clone()), seen))
+                    })
+                    .collect();
+                seen.pop();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Some(DataType::Range(r)) => r.to_string().blue().to_string(),
+            Some(DataType::Generator(g)) => g.to_string().blue().to_string(),
+            Some(DataType::Fiber(fiber)) => fiber.to_string().blue().to_string(),
             None => "nil".red().to_string(),
         };
         result
     }
 
-    fn visitor_runtime_error(&self, token: Option<&Token>, msg: &str) -> VisitorTypes {
+    pub fn visitor_runtime_error(&self, token: Option<&Token>, msg: &str) -> VisitorTypes {
         let token_clone = token.cloned();
         self.runtime_error(&token_clone, msg);
         VisitorTypes::RunTimeError {
@@ -183,97 +419,470 @@ impl Interpreter {
         }
     }
 
-    fn concatinate(&self, l: &str, r: &str) -> DataType {
-        let mut s = String::new();
-        s.push_str(l);
-        s.push_str(r);
-        DataType::String(s)
-    }
+    /// Puts the arguments of a call in the slots of the parameters they are for, named ones included.
+    /// A parameter without an argument gets None, the function uses its default. Kept out of visit_call_expr,
+    /// which is on the stack for every call a recursion goes through.
+    fn argument_slots(
+        &mut self,
+        expr: &Call,
+        function: &dyn LoxCallable,
+        arguments: Vec<DataType>,
+    ) -> Result<Vec<Option<DataType>>, Box<VisitorTypes>> {
+        let mut named = Vec::<(&Token, DataType)>::new();
+        for (name, expr) in &expr.named {
+            let data_type = match expr.accept(self) {
+                VisitorTypes::DataType(s) => s,
+                VisitorTypes::Return(r) => r,
+                VisitorTypes::RunTimeError { token: _, msg: _ } => Some(DataType::Nil),
+                _ => panic!("Interpreter entered impossible state."),
+            };
+            named.push((name, data_type.unwrap_or(DataType::Nil)));
+        }
+        let arity = function.arity();
+        let min_arity = function.min_arity();
+        let variadic = function.is_variadic();
+        let arity_error = |count: usize| {
+            if min_arity == arity && !variadic {
+                format!("Expected {} arguments but got {}.", arity, count)
+            } else if count > arity {
+                format!("Expected at most {} arguments but got {}.", arity, count)
+            } else {
+                format!(
+                    "Expected at least {} arguments but got {}.",
+                    min_arity, count
+                )
+            }
+        };
+        if arguments.len() > arity && !variadic {
+            let msg = arity_error(arguments.len());
+            return Err(Box::new(
+                self.visitor_runtime_error(Some(&expr.paren), &msg),
+            ));
+        }
 
-    fn runtime_error(&self, token: &Option<Token>, message: &str) -> Error {
-        error::runtime_error(token, message);
-        io::Error::new(ErrorKind::Other, message)
+        // The arguments past the arity of a variadic function stay at the end of the slots for its rest parameter.
+        let positional = arguments.len();
+        let mut slots: Vec<Option<DataType>> = arguments.into_iter().map(Some).collect();
+        if slots.len() < arity {
+            slots.resize(arity, None);
+        }
+        let names = function.parameter_names();
+        for (name, value) in named {
+            let index = match names.iter().position(|n| *n == name.lexeme) {
+                Some(index) => index,
+                None => {
+                    let msg = format!("No parameter named '{}'.", name.lexeme);
+                    return Err(Box::new(self.visitor_runtime_error(Some(name), &msg)));
+                }
+            };
+            if slots[index].is_some() {
+                let msg = format!("Parameter '{}' was given more than once.", name.lexeme);
+                return Err(Box::new(self.visitor_runtime_error(Some(name), &msg)));
+            }
+            slots[index] = Some(value);
+        }
+        // Parameters without a default come before the ones with a default, so these are the required ones.
+        if let Some(missing) = slots[..min_arity].iter().position(|s| s.is_none()) {
+            let msg = if expr.named.is_empty() {
+                arity_error(positional)
+            } else {
+                format!("Missing argument for parameter '{}'.", names[missing])
+            };
+            return Err(Box::new(
+                self.visitor_runtime_error(Some(&expr.paren), &msg),
+            ));
+        }
+
+        Ok(slots)
     }
-}
 
-impl ExprVisitor for Interpreter {
-    fn visit_assign_expr(&mut self, expr: &Assign) -> VisitorTypes {
-        let value = expr.value.accept(self);
-        match value {
-            VisitorTypes::DataType(d) => {
-                let data_type_value = match d {
-                    Some(d) => d,
-                    None => panic!("Interpreter entered an impossible state."),
-                };
+    /// Evaluates the index of an index expression, it has to be a whole number below the given length.
+    fn evaluate_index(
+        &mut self,
+        index: &Rc<dyn Expr>,
+        length: usize,
+        bracket: &Token,
+    ) -> Result<usize, Box<VisitorTypes>> {
+        match index.accept(self) {
+            VisitorTypes::DataType(index) => self.checked_index(index, length, bracket),
+            VisitorTypes::RunTimeError { token, msg } => {
+                Err(Box::new(VisitorTypes::RunTimeError { token, msg }))
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        }
+    }
 
-                let dyn_expr: Rc<dyn Expr> =
-                    Rc::new(Assign::new(expr.name.dup(), expr.value.clone()));
-                let hashed_expr = HashedExpr::new(dyn_expr);
+    fn checked_index(
+        &self,
+        index: Option<DataType>,
+        length: usize,
+        bracket: &Token,
+    ) -> Result<usize, Box<VisitorTypes>> {
+        let index = match index {
+            Some(DataType::Integer(n)) => n as f64,
+            Some(DataType::Number(n)) if n.fract() == 0.0 => n,
+            _ => {
+                let msg = "Index must be a whole number.";
+                return Err(Box::new(self.visitor_runtime_error(Some(bracket), msg)));
+            }
+        };
+        if index < 0.0 || index >= length as f64 {
+            let msg = "Index out of range.";
+            return Err(Box::new(self.visitor_runtime_error(Some(bracket), msg)));
+        }
+        Ok(index as usize)
+    }
 
-                if let Some(distance) = self.locals.borrow().get(&hashed_expr) {
-                    self.environment.borrow().borrow_mut().assign_at(
-                        *distance,
-                        &expr.name,
-                        data_type_value.clone(),
-                    );
-                } else {
-                    self.globals
-                        .borrow_mut()
-                        .assign(&expr.name, data_type_value.clone());
-                }
-                VisitorTypes::DataType(Some(data_type_value))
+    /// Evaluates the object and index of an assignment to a list or map element.
+    fn evaluate_element(
+        &mut self,
+        object: &Rc<dyn Expr>,
+        index: &Rc<dyn Expr>,
+        bracket: &Token,
+    ) -> Result<Element, Box<VisitorTypes>> {
+        match object.accept(self) {
+            VisitorTypes::DataType(Some(DataType::List(list))) => {
+                let length = list.borrow().len();
+                let index = self.evaluate_index(index, length, bracket)?;
+                Ok(Element::List(list, index))
+            }
+            VisitorTypes::DataType(Some(DataType::Map(map))) => {
+                let key = self.evaluate_key(index, bracket)?;
+                Ok(Element::Map(map, key))
+            }
+            VisitorTypes::RunTimeError { token, msg } => {
+                Err(Box::new(VisitorTypes::RunTimeError { token, msg }))
+            }
+            _ => {
+                let msg = "Only list and map elements can be assigned.";
+                Err(Box::new(self.visitor_runtime_error(Some(bracket), msg)))
             }
-            _ => self.visitor_runtime_error(Some(&expr.name.dup()), "Invalid assignment target."),
         }
     }
 
-    fn visit_binary_expr(&mut self, expr: &Binary) -> VisitorTypes {
-        let left = match expr.left.accept(self) {
-            VisitorTypes::DataType(d) => d,
+    /// Evaluates the key of a map index expression, map keys are strings.
+    fn evaluate_key(
+        &mut self,
+        key: &Rc<dyn Expr>,
+        bracket: &Token,
+    ) -> Result<String, Box<VisitorTypes>> {
+        match key.accept(self) {
+            VisitorTypes::DataType(Some(DataType::String(s))) => Ok(s),
+            VisitorTypes::RunTimeError { token, msg } => {
+                Err(Box::new(VisitorTypes::RunTimeError { token, msg }))
+            }
             _ => {
-                return self.visitor_runtime_error(
-                    Some(&expr.operator),
-                    "Expected a binary operation with proper data types.",
-                );
+                let msg = "Map keys must be strings.";
+                Err(Box::new(self.visitor_runtime_error(Some(bracket), msg)))
             }
-        };
+        }
+    }
 
-        let right = match expr.right.accept(self) {
-            VisitorTypes::DataType(d) => d,
+    /// The elements of a list destructured by [a, b], the list needs exactly one element per name.
+    fn list_parts(
+        &mut self,
+        value: Option<DataType>,
+        count: usize,
+        token: &Token,
+    ) -> Result<Vec<DataType>, Box<VisitorTypes>> {
+        match value {
+            Some(DataType::List(list)) => {
+                let length = list.borrow().len();
+                if length != count {
+                    let msg = format!("Expected {count} values to destructure but got {length}.");
+                    return Err(Box::new(self.visitor_runtime_error(Some(token), &msg)));
+                }
+                let elements = list.borrow().clone();
+                Ok(elements)
+            }
             _ => {
-                return self.visitor_runtime_error(
-                    Some(&expr.operator),
-                    "Expected a binary operation with proper data types.",
-                );
+                let msg = "Only lists can be destructured with [ ].";
+                Err(Box::new(self.visitor_runtime_error(Some(token), msg)))
             }
-        };
-        let calculation = match expr.operator.token_type {
+        }
+    }
+
+    /// The values of a map or an instance destructured by {x, y}, every name has to be a key or a property.
+    fn field_parts(
+        &mut self,
+        value: Option<DataType>,
+        names: &[Token],
+        brace: &Token,
+    ) -> Result<Vec<DataType>, Box<VisitorTypes>> {
+        let mut parts = Vec::<DataType>::new();
+        for name in names {
+            let part = match &value {
+                Some(DataType::Map(map)) => match map.borrow().get(&name.lexeme) {
+                    Some(value) => VisitorTypes::DataType(Some(value.clone())),
+                    None => VisitorTypes::RunTimeError {
+                        token: Some(name.dup()),
+                        msg: format!("Undefined key '{}'.", name.lexeme),
+                    },
+                },
+                Some(DataType::Instance(instance)) => instance.get(name, false, self),
+                _ => {
+                    let msg = "Only maps and instances can be destructured with { }.";
+                    return Err(Box::new(self.visitor_runtime_error(Some(brace), msg)));
+                }
+            };
+            match part {
+                VisitorTypes::DataType(d) => parts.push(d.unwrap_or(DataType::Nil)),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return Err(Box::new(self.visitor_runtime_error(token.as_ref(), &msg)));
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Assigns to a variable, a field or an element, the targets a destructuring assignment can have.
+    fn assign_target(&mut self, target: &Rc<dyn Expr>, value: DataType) -> VisitorTypes {
+        if let Some(variable) = target.as_any().downcast_ref::<Variable>() {
+            let expr: Rc<dyn Expr> = Rc::new(Variable::new(variable.name.dup()));
+            return self.assign_variable(&variable.name, &expr, value);
+        }
+        if let Some(get) = target.as_any().downcast_ref::<Get>() {
+            let object = match get.object.accept(self) {
+                VisitorTypes::DataType(d) => d,
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            };
+            let through_this = get.object.as_any().downcast_ref::<This>().is_some();
+            return self.set_property(object, &get.name, through_this, Some(value));
+        }
+        // The parser only allows variables, fields and list or map elements as the target.
+        let index = target.as_any().downcast_ref::<Index>().unwrap();
+        match self.evaluate_element(&index.object, &index.index, &index.bracket) {
+            Ok(element) => {
+                element.set(value.clone());
+                VisitorTypes::DataType(Some(value))
+            }
+            Err(e) => *e,
+        }
+    }
+
+    /// Evaluates a bound of a slice, a bound may be equal to the length: list[1:len(list)].
+    fn evaluate_slice_bound(
+        &mut self,
+        bound: &Option<Rc<dyn Expr>>,
+        default: usize,
+        length: usize,
+        bracket: &Token,
+    ) -> Result<usize, Box<VisitorTypes>> {
+        match bound {
+            Some(bound) => self.evaluate_index(bound, length + 1, bracket),
+            None => Ok(default),
+        }
+    }
+
+    /// Wraps the result of an integer operation.
+    /// When it overflowed, the configured overflow behavior decides between the float result and a runtime error.
+    fn integer_result(&self, result: Option<i64>, promoted: f64, operator: &Token) -> VisitorTypes {
+        match (result, config::get_int_overflow()) {
+            (Some(n), _) => VisitorTypes::DataType(Some(DataType::Integer(n))),
+            (None, IntOverflow::Promote) => {
+                VisitorTypes::DataType(Some(DataType::Number(promoted)))
+            }
+            (None, IntOverflow::Error) => {
+                self.visitor_runtime_error(Some(operator), "Integer overflow.")
+            }
+        }
+    }
+
+    /// Applies a binary operator to two evaluated operands.
+    /// Compound assignments use this as well, with the operator of the assignment: += adds like + does.
+    fn binary_operation(
+        &mut self,
+        left: Option<DataType>,
+        operator: &Token,
+        right: Option<DataType>,
+    ) -> VisitorTypes {
+        if !matches!(operator.token_type, TokenType::EqualEqual | TokenType::In)
+            && Operands::mixes_exact_and_float(left.as_ref(), right.as_ref())
+        {
+            let msg = "Can't mix exact and floating point numbers, convert them with decimal(), bigint() or toNumber().";
+            return self.visitor_runtime_error(Some(operator), msg);
+        }
+        let calculation = match operator.token_type {
             // There's extra logic for strings, this is so strings can be concatinated with the + operator.
-            TokenType::Plus => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => {
-                    return VisitorTypes::DataType(Some(DataType::Number(l + r)));
-                }
-                (None, None) => self.concatinate("nil", "nil"),
-                (None, Some(r)) => self.concatinate("nil", &r.to_string()),
-                (Some(l), None) => self.concatinate(&l.to_string(), "nil"),
-                (Some(l), Some(r)) => self.concatinate(&l.to_string(), &r.to_string()),
+            TokenType::Plus => match (Operands::new(left.as_ref(), right.as_ref()), left, right) {
+                (Some(Operands::Integer(l, r)), _, _) => {
+                    return self.integer_result(l.checked_add(r), l as f64 + r as f64, operator);
+                }
+                (Some(Operands::Number(l, r)), _, _) => DataType::Number(l + r),
+                (Some(Operands::BigInt(l, r)), _, _) => DataType::BigInt(l.add(&r)),
+                (Some(Operands::Decimal(l, r)), _, _) => DataType::Decimal(l.add(&r)),
+                (None, None, None) => self.concatinate("nil", "nil"),
+                (None, None, Some(r)) => self.concatinate("nil", &r.to_string()),
+                (None, Some(l), None) => self.concatinate(&l.to_string(), "nil"),
+                (None, Some(l), Some(r)) => self.concatinate(&l.to_string(), &r.to_string()),
             },
-            TokenType::Minus => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Number(l - r),
-                _ => {
-                    return self.visitor_runtime_error(Some(&expr.operator), "Expected a number.");
+            TokenType::Minus => match Operands::new(left.as_ref(), right.as_ref()) {
+                Some(Operands::Integer(l, r)) => {
+                    return self.integer_result(l.checked_sub(r), l as f64 - r as f64, operator);
                 }
+                Some(Operands::Number(l, r)) => DataType::Number(l - r),
+                Some(Operands::BigInt(l, r)) => DataType::BigInt(l.sub(&r)),
+                Some(Operands::Decimal(l, r)) => DataType::Decimal(l.sub(&r)),
+                None => return self.visitor_runtime_error(Some(operator), "Expected a number."),
             },
-            TokenType::Slash => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Number(l / r),
-                _ => {
-                    return self.visitor_runtime_error(Some(&expr.operator), "Expected a number.");
+            // Division of integers and floats always gives a float, exact numbers give an exact decimal,
+            // see Decimal::div for the precision.
+            // Dividing any kind of number by zero is a runtime error, for /, // and % alike.
+            TokenType::Slash => match Operands::new(left.as_ref(), right.as_ref()) {
+                Some(operands) if operands.has_zero_divisor() => {
+                    return self.visitor_runtime_error(Some(operator), "Division by zero.");
+                }
+                Some(Operands::BigInt(l, r)) => {
+                    DataType::Decimal(Decimal::from(l).div(&Decimal::from(r)).unwrap())
+                }
+                Some(Operands::Decimal(l, r)) => DataType::Decimal(l.div(&r).unwrap()),
+                Some(operands) => {
+                    let (l, r) = operands.as_numbers();
+                    DataType::Number(l / r)
+                }
+                None => return self.visitor_runtime_error(Some(operator), "Expected a number."),
+            },
+            TokenType::Star => match Operands::new(left.as_ref(), right.as_ref()) {
+                Some(Operands::Integer(l, r)) => {
+                    return self.integer_result(l.checked_mul(r), l as f64 * r as f64, operator);
+                }
+                Some(Operands::Number(l, r)) => DataType::Number(l * r),
+                Some(Operands::BigInt(l, r)) => DataType::BigInt(l.mul(&r)),
+                Some(Operands::Decimal(l, r)) => DataType::Decimal(l.mul(&r)),
+                None => return self.visitor_runtime_error(Some(operator), "Expected a number."),
+            },
+            // Floor division rounds towards negative infinity: -7 ~/ 2 is -4.
+            // The remainder takes the sign of the divisor, so it pairs with floor division: -7 % 2 is 1.
+            TokenType::TildeSlash | TokenType::Percent => {
+                let operands = match Operands::new(left.as_ref(), right.as_ref()) {
+                    Some(operands) if operands.has_zero_divisor() => {
+                        return self.visitor_runtime_error(Some(operator), "Division by zero.");
+                    }
+                    Some(operands) => operands,
+                    None => {
+                        return self.visitor_runtime_error(Some(operator), "Expected a number.")
+                    }
+                };
+                let floor_division = operator.token_type == TokenType::TildeSlash;
+                match operands {
+                    Operands::Integer(l, r) if floor_division => {
+                        let promoted = (l as f64 / r as f64).floor();
+                        return self.integer_result(floor_div(l, r), promoted, operator);
+                    }
+                    Operands::Integer(l, r) => DataType::Integer(floor_mod(l, r)),
+                    Operands::Number(l, r) if floor_division => DataType::Number((l / r).floor()),
+                    Operands::Number(l, r) => DataType::Number(l - r * (l / r).floor()),
+                    Operands::BigInt(l, r) => {
+                        let (quotient, remainder) = l.div_mod_floor(&r).unwrap();
+                        DataType::BigInt(if floor_division { quotient } else { remainder })
+                    }
+                    Operands::Decimal(l, r) => {
+                        let (quotient, remainder) = l.div_mod_floor(&r).unwrap();
+                        DataType::Decimal(if floor_division { quotient } else { remainder })
+                    }
+                }
+            }
+            // A negative integer exponent gives a float: 2 ** -1 is 0.5.
+            // Exact numbers only take a whole, non negative exponent.
+            TokenType::StarStar => match Operands::new(left.as_ref(), right.as_ref()) {
+                Some(Operands::Integer(l, r)) if r >= 0 => {
+                    let result = u32::try_from(r).ok().and_then(|r| l.checked_pow(r));
+                    return self.integer_result(result, (l as f64).powf(r as f64), operator);
+                }
+                // The exponent is capped by the size of the result, see BigInt::pow.
+                Some(Operands::BigInt(l, r)) => match r.to_u32() {
+                    Some(r) => match l.pow(r) {
+                        Some(power) => DataType::BigInt(power),
+                        None => {
+                            let msg = "The result of the power has too many digits.";
+                            return self.visitor_runtime_error(Some(operator), msg);
+                        }
+                    },
+                    None => {
+                        let msg = "Exponent must be a whole number that is not negative.";
+                        return self.visitor_runtime_error(Some(operator), msg);
+                    }
+                },
+                Some(Operands::Decimal(l, r)) => match r.to_bigint().and_then(|r| r.to_u32()) {
+                    Some(r) => match l.pow(r) {
+                        Some(power) => DataType::Decimal(power),
+                        None => {
+                            let msg = "The result of the power has too many digits.";
+                            return self.visitor_runtime_error(Some(operator), msg);
+                        }
+                    },
+                    None => {
+                        let msg = "Exponent must be a whole number that is not negative.";
+                        return self.visitor_runtime_error(Some(operator), msg);
+                    }
+                },
+                Some(operands) => {
+                    let (l, r) = operands.as_numbers();
+                    DataType::Number(l.powf(r))
                 }
+                None => return self.visitor_runtime_error(Some(operator), "Expected a number."),
             },
-            TokenType::Star => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Number(l * r),
+            // Floats without a fractional part are accepted as well: 6.0 & 3 is 2.
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => {
+                let (l, r) = match (whole_number(left.as_ref()), whole_number(right.as_ref())) {
+                    (Some(l), Some(r)) => (l, r),
+                    _ => {
+                        let msg = bitwise_error(left.as_ref(), right.as_ref());
+                        return self.visitor_runtime_error(Some(operator), msg);
+                    }
+                };
+                match operator.token_type {
+                    TokenType::Ampersand => DataType::Integer(l & r),
+                    TokenType::Pipe => DataType::Integer(l | r),
+                    TokenType::Caret => DataType::Integer(l ^ r),
+                    _ if !(0..64).contains(&r) => {
+                        let msg = "Shift amount must be between 0 and 63.";
+                        return self.visitor_runtime_error(Some(operator), msg);
+                    }
+                    // Bits shifted out of a left shift overflow, like multiplying by a power of two does.
+                    TokenType::LessLess => {
+                        let shifted = l << r;
+                        let result = if shifted >> r == l {
+                            Some(shifted)
+                        } else {
+                            None
+                        };
+                        let promoted = l as f64 * 2f64.powi(r as i32);
+                        return self.integer_result(result, promoted, operator);
+                    }
+                    _ => DataType::Integer(l >> r),
+                }
+            }
+            // A number is in a range when the range would give it, a string is in a string when it's part of it.
+            TokenType::In => match (left, right) {
+                (left, Some(DataType::Range(range))) => {
+                    DataType::Bool(whole_number(left.as_ref()).is_some_and(|n| range.contains(n)))
+                }
+                (left, Some(DataType::List(list))) => {
+                    let left = left.unwrap_or(DataType::Nil);
+                    DataType::Bool(list.borrow().iter().any(|e| self.is_equal(&left, e)))
+                }
+                (left, Some(DataType::Map(map))) => DataType::Bool(
+                    matches!(left, Some(DataType::String(key)) if map.borrow().contains_key(&key)),
+                ),
+                (Some(DataType::String(part)), Some(DataType::String(s))) => {
+                    DataType::Bool(s.contains(&part))
+                }
+                (_, Some(DataType::String(_))) => {
+                    let msg = "Only a string can be in a string.";
+                    return self.visitor_runtime_error(Some(operator), msg);
+                }
                 _ => {
-                    return self.visitor_runtime_error(Some(&expr.operator), "Expected a number.");
+                    let msg = "Can only test membership of ranges, lists, maps and strings.";
+                    return self.visitor_runtime_error(Some(operator), msg);
                 }
             },
             TokenType::EqualEqual => match (left, right) {
@@ -282,70 +891,554 @@ impl ExprVisitor for Interpreter {
                 (Some(_), None) => DataType::Bool(false),
                 (None, Some(_)) => DataType::Bool(false),
             },
-            TokenType::Greater => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Bool(l > r),
-                _ => {
-                    return self.visitor_runtime_error(
-                        Some(&expr.operator),
-                        "Expected a binary operation.",
-                    );
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => match Operands::new(left.as_ref(), right.as_ref()) {
+                Some(operands) => {
+                    let ordering = operands.compare();
+                    DataType::Bool(match operator.token_type {
+                        TokenType::Greater => ordering == Some(Ordering::Greater),
+                        TokenType::GreaterEqual => {
+                            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                        }
+                        TokenType::Less => ordering == Some(Ordering::Less),
+                        _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    })
+                }
+                None => {
+                    return self
+                        .visitor_runtime_error(Some(operator), "Expected a binary operation.");
                 }
             },
-            TokenType::GreaterEqual => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Bool(l >= r),
-                _ => {
-                    return self.visitor_runtime_error(
-                        Some(&expr.operator),
-                        "Expected a binary operation.",
-                    );
+            _ => {
+                return self.visitor_runtime_error(Some(operator), "Invalid binary operation.");
+            }
+        };
+        VisitorTypes::DataType(Some(calculation))
+    }
+
+    /// Looks up the property of an evaluated object.
+    fn get_property(&mut self, object: Option<DataType>, expr: &Get) -> VisitorTypes {
+        let err_msg = "Only instances and classes have properties.";
+        let through_this = expr.object.as_any().downcast_ref::<This>().is_some();
+        let res = match object {
+            Some(DataType::Instance(instance)) => instance.get(&expr.name, through_this, self),
+            Some(DataType::Class(class)) => class.get(&expr.name),
+            Some(DataType::String(s)) => match StringMethod::new(&expr.name.lexeme, s) {
+                Some(method) => VisitorTypes::DataType(Some(DataType::Native(LoxNative {
+                    function: Rc::new(method),
+                }))),
+                None => {
+                    let msg = format!("Undefined string method '{}'.", expr.name.lexeme);
+                    return self.visitor_runtime_error(Some(&expr.name), &msg);
                 }
             },
-            TokenType::Less => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Bool(l < r),
+            Some(DataType::Generator(generator)) => match expr.name.lexeme.as_str() {
+                "next" => VisitorTypes::DataType(Some(DataType::Native(LoxNative {
+                    function: Rc::new(GeneratorNext::new(generator)),
+                }))),
+                "isDone" => VisitorTypes::DataType(Some(DataType::Bool(generator.is_done()))),
                 _ => {
-                    return self.visitor_runtime_error(
-                        Some(&expr.operator),
-                        "Expected a binary operation.",
-                    );
+                    let msg = format!("Undefined generator property '{}'.", expr.name.lexeme);
+                    return self.visitor_runtime_error(Some(&expr.name), &msg);
                 }
             },
-            TokenType::LessEqual => match (left, right) {
-                (Some(DataType::Number(l)), Some(DataType::Number(r))) => DataType::Bool(l <= r),
-                _ => {
-                    return self.visitor_runtime_error(
-                        Some(&expr.operator),
-                        "Expected a binary operation.",
-                    );
+            Some(DataType::Fiber(fiber)) => {
+                let property = match expr.name.lexeme.as_str() {
+                    "resume" => DataType::Native(LoxNative {
+                        function: Rc::new(FiberResume::new(fiber)),
+                    }),
+                    "isDone" => DataType::Bool(fiber.is_done()),
+                    _ => {
+                        let msg = format!("Undefined fiber property '{}'.", expr.name.lexeme);
+                        return self.visitor_runtime_error(Some(&expr.name), &msg);
+                    }
+                };
+                VisitorTypes::DataType(Some(property))
+            }
+            Some(DataType::Native(native)) => match native.function.property(&expr.name.lexeme) {
+                Some(property) => VisitorTypes::DataType(Some(property)),
+                None => {
+                    let msg = format!("Undefined property '{}'.", expr.name.lexeme);
+                    return self.visitor_runtime_error(Some(&expr.name), &msg);
                 }
             },
-            _ => {
-                return self
-                    .visitor_runtime_error(Some(&expr.operator), "Invalid binary operation.");
+            Some(DataType::Range(range)) => {
+                let property = match expr.name.lexeme.as_str() {
+                    "start" => DataType::Integer(range.start()),
+                    "end" => DataType::Integer(range.end()),
+                    "step" => DataType::Integer(range.step()),
+                    "stepBy" => DataType::Native(LoxNative {
+                        function: Rc::new(StepBy::new(range)),
+                    }),
+                    _ => {
+                        let msg = format!("Undefined range property '{}'.", expr.name.lexeme);
+                        return self.visitor_runtime_error(Some(&expr.name), &msg);
+                    }
+                };
+                VisitorTypes::DataType(Some(property))
             }
+            _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
         };
-        VisitorTypes::DataType(Some(calculation))
+
+        if let VisitorTypes::RunTimeError { token, msg } = res {
+            return self.visitor_runtime_error(token.as_ref(), &msg);
+        }
+        res
     }
 
-    fn visit_call_expr(&mut self, expr: &Call) -> VisitorTypes {
-        let token = expr.paren.dup();
-        let callee = match expr.callee.accept(self) {
-            VisitorTypes::DataType(d) => d,
-            VisitorTypes::RunTimeError { token, msg } => {
-                return VisitorTypes::RunTimeError { token, msg };
-            }
-            _ => panic!("Interpreter entered impossible state."),
-        };
-        let mut arguments = Vec::<DataType>::new();
-        for expr in &expr.arguments {
+    /// Sets the property of an evaluated object, gives the assigned value.
+    fn set_property(
+        &mut self,
+        object: Option<DataType>,
+        name: &Token,
+        through_this: bool,
+        value: Option<DataType>,
+    ) -> VisitorTypes {
+        match object {
+            Some(DataType::Instance(instance)) => {
+                if let VisitorTypes::RunTimeError { token, msg } =
+                    instance.set(name, value.clone(), through_this, self)
+                {
+                    return self.visitor_runtime_error(token.as_ref(), &msg);
+                }
+            }
+            Some(DataType::Class(class)) => {
+                class.set(name, value.clone());
+            }
+            _ => {
+                let msg = "Only instances and classes have fields.";
+                return self.visitor_runtime_error(Some(name), msg);
+            }
+        }
+        VisitorTypes::DataType(value)
+    }
+
+    /// Calculates the new value of an update expression from the current value of its target.
+    /// Gives the new value and the value of the expression itself, which is the old value for a postfix update.
+    fn updated_value(
+        &mut self,
+        old: Option<DataType>,
+        expr: &Update,
+    ) -> Result<(DataType, DataType), Box<VisitorTypes>> {
+        let value = match &expr.value {
+            Some(value) => match value.accept(self) {
+                VisitorTypes::DataType(d) => d,
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return Err(Box::new(VisitorTypes::RunTimeError { token, msg }));
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            },
+            // ++ and -- add or subtract a one of the same kind of number.
+            None => Some(match &old {
+                Some(DataType::Integer(_)) => DataType::Integer(1),
+                Some(DataType::Number(_)) => DataType::Number(1.0),
+                Some(DataType::BigInt(_)) => DataType::BigInt(BigInt::from(1)),
+                Some(DataType::Decimal(_)) => DataType::Decimal(Decimal::from(BigInt::from(1))),
+                _ => {
+                    let error =
+                        self.visitor_runtime_error(Some(&expr.operator), "Expected a number.");
+                    return Err(Box::new(error));
+                }
+            }),
+        };
+        let token_type = match expr.operator.token_type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            _ => TokenType::Slash,
+        };
+        // The operator keeps the lexeme and position of the update, so errors point at the += or ++.
+        let operator = Token::new(
+            token_type,
+            expr.operator.lexeme.clone(),
+            None,
+            expr.operator.line,
+            expr.operator.pos,
+            expr.operator.offset,
+        );
+        let new = match self.binary_operation(old.clone(), &operator, value) {
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+            error => return Err(Box::new(error)),
+        };
+        let result = if expr.prefix {
+            new.clone()
+        } else {
+            old.unwrap_or(DataType::Nil)
+        };
+        Ok((new, result))
+    }
+
+    /// The resolver rejects declaring a local name twice, globals can be declared again unless they are constant.
+    fn check_redeclaration(&self, name: &Token) -> Result<(), Box<VisitorTypes>> {
+        if self.environment().borrow().is_constant(&name.lexeme) {
+            let msg = format!("Can't redeclare constant '{}'.", name.lexeme);
+            return Err(Box::new(self.visitor_runtime_error(Some(name), &msg)));
+        }
+        Ok(())
+    }
+
+    /// Runs the body of a for-in loop once, in a new environment that holds the loop variable.
+    /// Every iteration has its own variable, so closures made in the body each see their own value.
+    fn run_iteration(&mut self, stmt: &ForIn, value: DataType) -> VisitorTypes {
+        let mut environment = Environment::new_enclosing(self.environment.borrow().clone());
+        environment.define(stmt.name.dup().lexeme, value);
+        self.execute_block(&Rc::new(vec![stmt.body.clone()]), environment)
+    }
+
+    /// Gets ready to go over the values of a for-in loop. Ranges and generators make their values one at a time.
+    /// An object of a user class is iterated with the object that its iterator() method gives,
+    /// the next() method of that object gives the values. Before every call of next() the loop reads isDone of
+    /// the iterator, a field, a getter or a method, and ends when it's true. Then next() can give nil as a value,
+    /// an iterator without isDone ends at the first nil instead.
+    pub fn iteration(
+        &mut self,
+        iterable: Option<DataType>,
+        keyword: &Token,
+    ) -> Result<Iteration, Box<VisitorTypes>> {
+        let msg = "Can only iterate over lists, maps, strings, generators and objects with an iterator() method.";
+        let object = match iterable {
+            Some(DataType::List(list)) => {
+                let values = list.borrow().clone();
+                return Ok(Iteration::Values(Box::new(values.into_iter())));
+            }
+            Some(DataType::Map(map)) => {
+                let keys: Vec<DataType> =
+                    map.borrow().keys().cloned().map(DataType::String).collect();
+                return Ok(Iteration::Values(Box::new(keys.into_iter())));
+            }
+            Some(DataType::String(s)) => {
+                let chars: Vec<DataType> =
+                    s.chars().map(|c| DataType::String(c.to_string())).collect();
+                return Ok(Iteration::Values(Box::new(chars.into_iter())));
+            }
+            Some(DataType::Range(range)) => {
+                return Ok(Iteration::Values(Box::new(
+                    range.iter().map(DataType::Integer),
+                )));
+            }
+            Some(DataType::Generator(generator)) => return Ok(Iteration::Generator(generator)),
+            Some(DataType::Instance(object)) => object,
+            _ => return Err(Box::new(self.visitor_runtime_error(Some(keyword), msg))),
+        };
+        let iterator = match object.bound_method("iterator") {
+            Some(method) => self.call_iterator_method(&method, keyword),
+            None => return Err(Box::new(self.visitor_runtime_error(Some(keyword), msg))),
+        };
+        let (iterator, next) = match iterator {
+            VisitorTypes::DataType(Some(DataType::Instance(iterator))) => {
+                let next = iterator.bound_method("next");
+                (iterator, next)
+            }
+            // A user class can hand out a generator as its iterator.
+            VisitorTypes::DataType(Some(DataType::Generator(generator))) => {
+                return Ok(Iteration::Generator(generator));
+            }
+            VisitorTypes::RunTimeError { token, msg } => {
+                return Err(Box::new(VisitorTypes::RunTimeError { token, msg }));
+            }
+            _ => {
+                let msg = "iterator() must return an object with a next() method.";
+                return Err(Box::new(self.visitor_runtime_error(Some(keyword), msg)));
+            }
+        };
+        match next {
+            Some(next) => Ok(Iteration::Object(iterator, next)),
+            None => {
+                let msg = "iterator() must return an object with a next() method.";
+                Err(Box::new(self.visitor_runtime_error(Some(keyword), msg)))
+            }
+        }
+    }
+
+    /// The next value of a for-in loop, None when there are no values left.
+    pub fn next_iteration(
+        &mut self,
+        iteration: &mut Iteration,
+        keyword: &Token,
+    ) -> Result<Option<DataType>, Box<VisitorTypes>> {
+        match iteration {
+            Iteration::Values(values) => Ok(values.next()),
+            Iteration::Object(iterator, next) => {
+                let has_done = iterator.has_property("isDone");
+                if has_done && self.iterator_is_done(iterator, keyword)? {
+                    return Ok(None);
+                }
+                match self.call_iterator_method(next, keyword) {
+                    VisitorTypes::DataType(None) | VisitorTypes::DataType(Some(DataType::Nil))
+                        if !has_done =>
+                    {
+                        Ok(None)
+                    }
+                    VisitorTypes::DataType(value) => Ok(Some(value.unwrap_or(DataType::Nil))),
+                    result => Err(Box::new(result)),
+                }
+            }
+            Iteration::Generator(generator) => match generator.resume(self) {
+                Err(e) => Err(Box::new(self.iteration_error(*e, keyword))),
+                values => values,
+            },
+        }
+    }
+
+    /// Reads isDone of an iterator, a method is called.
+    fn iterator_is_done(
+        &mut self,
+        iterator: &Rc<LoxInstance>,
+        keyword: &Token,
+    ) -> Result<bool, Box<VisitorTypes>> {
+        let name = Token::new(
+            TokenType::Identifier,
+            "isDone".to_string(),
+            None,
+            keyword.line,
+            keyword.pos,
+            keyword.offset,
+        );
+        let done = match iterator.get(&name, false, self) {
+            VisitorTypes::DataType(Some(DataType::Function(method))) => {
+                self.call_iterator_method(&method, keyword)
+            }
+            error @ VisitorTypes::RunTimeError { .. } => self.iteration_error(error, keyword),
+            done => done,
+        };
+        match done {
+            VisitorTypes::DataType(done) => Ok(self.is_truthy(&done.unwrap_or(DataType::Nil))),
+            error => Err(Box::new(error)),
+        }
+    }
+
+    fn call_iterator_method(&mut self, method: &LoxFunction, keyword: &Token) -> VisitorTypes {
+        match method.call(self, Vec::new()) {
+            error @ VisitorTypes::RunTimeError { .. } => self.iteration_error(error, keyword),
+            result => result,
+        }
+    }
+
+    /// Reports a runtime error that ends a for-in loop, at its own token or at the 'in' of the loop without one.
+    pub fn iteration_error(&self, error: VisitorTypes, keyword: &Token) -> VisitorTypes {
+        match error {
+            VisitorTypes::RunTimeError { token, msg } => {
+                self.visitor_runtime_error(Some(token.as_ref().unwrap_or(keyword)), &msg)
+            }
+            error => error,
+        }
+    }
+
+    /// Copies the part of a list or string between start and the exclusive end.
+    fn slice(
+        &self,
+        object: Option<DataType>,
+        start: usize,
+        end: usize,
+        bracket: &Token,
+    ) -> VisitorTypes {
+        if start > end {
+            let msg = "Slice start can't be after its end.";
+            return self.visitor_runtime_error(Some(bracket), msg);
+        }
+        let slice = match object {
+            Some(DataType::List(list)) => {
+                let elements = list.borrow()[start..end].to_vec();
+                DataType::List(Rc::new(RefCell::new(elements)))
+            }
+            Some(DataType::String(s)) => {
+                DataType::String(s.chars().skip(start).take(end - start).collect())
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        VisitorTypes::DataType(Some(slice))
+    }
+
+    /// Slices a list or string with a range: list[1..3] is list[1:3] and list[1..=3] is list[1:4].
+    fn slice_with_range(
+        &self,
+        object: Option<DataType>,
+        range: LoxRange,
+        length: usize,
+        bracket: &Token,
+    ) -> VisitorTypes {
+        let (start, end) = match range.slice_bounds() {
+            Some(bounds) => bounds,
+            None => {
+                let msg = "Can only slice with a range that has a step of 1.";
+                return self.visitor_runtime_error(Some(bracket), msg);
+            }
+        };
+        if start < 0 || end < 0 || start as u64 > length as u64 || end as u64 > length as u64 {
+            return self.visitor_runtime_error(Some(bracket), "Index out of range.");
+        }
+        self.slice(object, start as usize, end as usize, bracket)
+    }
+
+    /// Checks if a value matches a pattern of a match arm.
+    fn matches_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &DataType,
+    ) -> Result<bool, Box<VisitorTypes>> {
+        let matched = match pattern {
+            Pattern::Literal { value: literal, .. } => self.is_equal(literal, value),
+            Pattern::Range { range, .. } => {
+                whole_number(Some(value)).is_some_and(|n| range.contains(n))
+            }
+            Pattern::Binding(_) | Pattern::Wildcard(_) => true,
+            Pattern::Class { name, class, .. } => match class.accept(self) {
+                VisitorTypes::DataType(Some(DataType::Class(class))) => {
+                    matches!(value, DataType::Instance(i) if i.class().is_same(&class))
+                }
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return Err(Box::new(VisitorTypes::RunTimeError { token, msg }));
+                }
+                _ => {
+                    let msg = format!("'{}' in a pattern is not a class.", name.lexeme);
+                    return Err(Box::new(self.visitor_runtime_error(Some(name), &msg)));
+                }
+            },
+        };
+        Ok(matched)
+    }
+
+    /// Finds the first arm of a match statement whose pattern matches and whose guard allows it.
+    /// The statement is evaluated in the given environment. Gives back the body of the arm
+    /// with the environment to run it in, which holds the name the pattern binds.
+    pub fn select_arm(
+        &mut self,
+        stmt: &Match,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<ArmBody>, Box<VisitorTypes>> {
+        let value = match self.evaluate_in(&stmt.value, Rc::clone(&environment)) {
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+            VisitorTypes::RunTimeError { token, msg } => {
+                return Err(Box::new(VisitorTypes::RunTimeError { token, msg }));
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        for arm in &stmt.arms {
+            for pattern in &arm.patterns {
+                if !self.matches_pattern(pattern, &value)? {
+                    continue;
+                }
+                let mut arm_environment = Environment::new_enclosing(Rc::clone(&environment));
+                if let Some(name) = pattern.binding() {
+                    arm_environment.define(name.dup().lexeme, value.clone());
+                }
+                let arm_environment = Rc::new(RefCell::new(arm_environment));
+                let guard = match &arm.guard {
+                    Some(guard) => match self.evaluate_in(guard, Rc::clone(&arm_environment)) {
+                        VisitorTypes::DataType(d) => self.is_truthy(&d.unwrap_or(DataType::Nil)),
+                        result => return Err(Box::new(result)),
+                    },
+                    None => true,
+                };
+                if guard {
+                    return Ok(Some((Rc::clone(&arm.body), arm_environment)));
+                }
+                // The guard was false, the patterns of this arm don't need to be tried anymore.
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn concatinate(&self, l: &str, r: &str) -> DataType {
+        let mut s = String::new();
+        s.push_str(l);
+        s.push_str(r);
+        DataType::String(s)
+    }
+
+    fn runtime_error(&self, token: &Option<Token>, message: &str) -> Error {
+        error::runtime_error(token, message);
+        io::Error::new(ErrorKind::Other, message)
+    }
+}
+
+impl ExprVisitor for Interpreter {
+    fn visit_assign_expr(&mut self, expr: &Assign) -> VisitorTypes {
+        let value = expr.value.accept(self);
+        match value {
+            VisitorTypes::DataType(d) => {
+                let data_type_value = match d {
+                    Some(d) => d,
+                    None => panic!("Interpreter entered an impossible state."),
+                };
+
+                let dyn_expr: Rc<dyn Expr> =
+                    Rc::new(Assign::new(expr.name.dup(), expr.value.clone()));
+                self.assign_variable(&expr.name, &dyn_expr, data_type_value)
+            }
+            _ => self.visitor_runtime_error(Some(&expr.name.dup()), "Invalid assignment target."),
+        }
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Binary) -> VisitorTypes {
+        let left = match expr.left.accept(self) {
+            VisitorTypes::DataType(d) => d,
+            _ => {
+                return self.visitor_runtime_error(
+                    Some(&expr.operator),
+                    "Expected a binary operation with proper data types.",
+                );
+            }
+        };
+
+        let right = match expr.right.accept(self) {
+            VisitorTypes::DataType(d) => d,
+            _ => {
+                return self.visitor_runtime_error(
+                    Some(&expr.operator),
+                    "Expected a binary operation with proper data types.",
+                );
+            }
+        };
+        self.binary_operation(left, &expr.operator, right)
+    }
+
+    fn visit_call_expr(&mut self, expr: &Call) -> VisitorTypes {
+        let token = expr.paren.dup();
+        // The stack of a fiber is a lot smaller than the one of the main thread and grows down to its limit.
+        let here = 0u8;
+        if self
+            .stack_limit
+            .is_some_and(|limit| (&here as *const u8 as usize) < limit)
+        {
+            return self.visitor_runtime_error(Some(&token), "Stack overflow.");
+        }
+        let callee = match expr.callee.accept(self) {
+            // obj?.method() skips the call when obj is nil, the nil from the property access is not called.
+            VisitorTypes::DataType(_) if self.short_circuit => {
+                return VisitorTypes::DataType(Some(DataType::Nil));
+            }
+            VisitorTypes::DataType(d) => d,
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let mut arguments = Vec::<DataType>::new();
+        for expr in &expr.arguments {
             let data_type = match expr.accept(self) {
                 VisitorTypes::DataType(s) => s,
                 VisitorTypes::Return(r) => r,
                 VisitorTypes::RunTimeError { token: _, msg: _ } => Some(DataType::Nil),
                 _ => panic!("Interpreter entered impossible state."),
             };
-            if let Some(d) = data_type {
-                arguments.push(d);
+            // f(...xs) passes the elements of xs as separate arguments.
+            if let Some(spread) = expr.as_any().downcast_ref::<Spread>() {
+                match data_type {
+                    Some(DataType::List(list)) => arguments.extend(list.borrow().iter().cloned()),
+                    _ => {
+                        return self.visitor_runtime_error(
+                            Some(&spread.operator),
+                            "Can only spread lists.",
+                        );
+                    }
+                }
+                continue;
             }
+            arguments.push(data_type.unwrap_or(DataType::Nil));
         }
         let function: Rc<dyn LoxCallable> = if let Some(c) = callee {
             match c {
@@ -363,46 +1456,148 @@ impl ExprVisitor for Interpreter {
             return self
                 .visitor_runtime_error(Some(&token), "Can only call functions and classes.");
         };
-        if arguments.len() != function.arity() {
-            let msg = format!(
-                "Expected {} arguments but got {}.",
-                function.arity(),
-                arguments.len()
-            );
-            return self.visitor_runtime_error(Some(&token), &msg);
+        let slots = match self.argument_slots(expr, function.as_ref(), arguments) {
+            Ok(slots) => slots,
+            Err(e) => return *e,
+        };
+        match function.call_with_defaults(self, slots) {
+            VisitorTypes::RunTimeError { token: None, msg } => {
+                self.visitor_runtime_error(Some(&token), &msg)
+            }
+            result => result,
         }
+    }
 
-        VisitorTypes::DataType(Some(function.call(self, arguments)))
+    fn visit_destructure_expr(&mut self, expr: &Destructure) -> VisitorTypes {
+        let value = match expr.value.accept(self) {
+            VisitorTypes::DataType(d) => d,
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let parts = match self.list_parts(value.clone(), expr.targets.len(), &expr.equals) {
+            Ok(parts) => parts,
+            Err(e) => return *e,
+        };
+        for (target, part) in expr.targets.iter().zip(parts) {
+            if let VisitorTypes::RunTimeError { token, msg } = self.assign_target(target, part) {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+        }
+        VisitorTypes::DataType(value)
     }
 
     fn visit_get_expr(&mut self, expr: &Get) -> VisitorTypes {
-        let err_msg = "Only instances have properties.";
+        let err_msg = "Only instances and classes have properties.";
         let object = match expr.object.accept(self) {
+            VisitorTypes::DataType(_) if self.short_circuit => {
+                return VisitorTypes::DataType(Some(DataType::Nil));
+            }
             VisitorTypes::DataType(d) => d,
             _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
         };
-        let res = match object {
-            Some(DataType::Instance(instance)) => instance.get(&expr.name),
-            _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
-        };
-
-        if let VisitorTypes::RunTimeError { token, msg } = res {
-            return self.visitor_runtime_error(token.as_ref(), &msg);
+        // obj?.field is nil when obj is nil, and so is the rest of the chain it is in.
+        if expr.optional && matches!(object, None | Some(DataType::Nil)) {
+            self.short_circuit = true;
+            return VisitorTypes::DataType(Some(DataType::Nil));
         }
-        res
+        self.get_property(object, expr)
     }
 
     fn visit_grouping_expr(&mut self, expr: &Grouping) -> VisitorTypes {
         expr.expression.accept(self)
     }
 
+    fn visit_index_expr(&mut self, expr: &Index) -> VisitorTypes {
+        let object = match expr.object.accept(self) {
+            VisitorTypes::DataType(_) if self.short_circuit => {
+                return VisitorTypes::DataType(Some(DataType::Nil));
+            }
+            VisitorTypes::DataType(d) => d,
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let length = match &object {
+            Some(DataType::List(list)) => list.borrow().len(),
+            // Strings are indexed by character, not by byte.
+            Some(DataType::String(s)) => s.chars().count(),
+            // A key that isn't in the map gives nil.
+            Some(DataType::Map(map)) => {
+                return match self.evaluate_key(&expr.index, &expr.bracket) {
+                    Ok(key) => VisitorTypes::DataType(Some(
+                        map.borrow().get(&key).cloned().unwrap_or(DataType::Nil),
+                    )),
+                    Err(e) => *e,
+                };
+            }
+            _ => {
+                let msg = "Only lists, maps and strings can be indexed.";
+                return self.visitor_runtime_error(Some(&expr.bracket), msg);
+            }
+        };
+        let index = match expr.index.accept(self) {
+            VisitorTypes::DataType(Some(DataType::Range(range))) => {
+                return self.slice_with_range(object, range, length, &expr.bracket);
+            }
+            VisitorTypes::DataType(d) => d,
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let index = match self.checked_index(index, length, &expr.bracket) {
+            Ok(index) => index,
+            Err(e) => return *e,
+        };
+        let element = match object {
+            Some(DataType::List(list)) => list.borrow()[index].clone(),
+            Some(DataType::String(s)) => {
+                DataType::String(s.chars().nth(index).unwrap().to_string())
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        VisitorTypes::DataType(Some(element))
+    }
+
+    /// Every part is turned into a string the same way print does.
+    fn visit_interpolation_expr(&mut self, expr: &Interpolation) -> VisitorTypes {
+        let mut result = String::new();
+        for part in &expr.parts {
+            match part.accept(self) {
+                VisitorTypes::DataType(d) => result.push_str(&self.stringify_helper(d)),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            }
+        }
+        VisitorTypes::DataType(Some(DataType::String(result)))
+    }
+
+    fn visit_list_expr(&mut self, expr: &List) -> VisitorTypes {
+        let mut elements = Vec::<DataType>::new();
+        for element in &expr.elements {
+            match element.accept(self) {
+                VisitorTypes::DataType(d) => elements.push(d.unwrap_or(DataType::Nil)),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            }
+        }
+        VisitorTypes::DataType(Some(DataType::List(Rc::new(RefCell::new(elements)))))
+    }
+
     fn visit_literal_expr(&mut self, expr: &Literal) -> VisitorTypes {
         VisitorTypes::DataType(expr.value.clone())
     }
 
     fn visit_logical_expr(&mut self, expr: &Logical) -> VisitorTypes {
         let left = match expr.left.accept(self) {
-            VisitorTypes::DataType(Some(d)) => d,
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
             _ => {
                 return self.visitor_runtime_error(
                     Some(&expr.operator),
@@ -410,18 +1605,97 @@ impl ExprVisitor for Interpreter {
                 );
             }
         };
-        if expr.operator.token_type == TokenType::Or {
-            if self.is_truthy(&left) {
-                return VisitorTypes::DataType(Some(left));
+        match expr.operator.token_type {
+            TokenType::Or => {
+                if self.is_truthy(&left) {
+                    return VisitorTypes::DataType(Some(left));
+                }
+            }
+            // Only nil is replaced, false is kept: false ?? true is false.
+            TokenType::QuestionQuestion => {
+                if !matches!(left, DataType::Nil) {
+                    return VisitorTypes::DataType(Some(left));
+                }
+            }
+            _ => {
+                if !self.is_truthy(&left) {
+                    return VisitorTypes::DataType(Some(left));
+                }
             }
-        } else if !self.is_truthy(&left) {
-            return VisitorTypes::DataType(Some(left));
         }
         expr.right.accept(self)
     }
 
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> VisitorTypes {
+        let condition = match expr.condition.accept(self) {
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        if self.is_truthy(&condition) {
+            expr.then_branch.accept(self)
+        } else {
+            expr.else_branch.accept(self)
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Map) -> VisitorTypes {
+        let mut entries = BTreeMap::<String, DataType>::new();
+        for (key, value) in &expr.entries {
+            let key = match key.accept(self) {
+                VisitorTypes::DataType(Some(DataType::String(s))) => s,
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => {
+                    return self
+                        .visitor_runtime_error(Some(&expr.brace), "Map keys must be strings.");
+                }
+            };
+            match value.accept(self) {
+                VisitorTypes::DataType(d) => entries.insert(key, d.unwrap_or(DataType::Nil)),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            };
+        }
+        VisitorTypes::DataType(Some(DataType::Map(Rc::new(RefCell::new(entries)))))
+    }
+
+    fn visit_optional_chain_expr(&mut self, expr: &OptionalChain) -> VisitorTypes {
+        let result = expr.expression.accept(self);
+        self.short_circuit = false;
+        result
+    }
+
+    fn visit_range_expr(&mut self, expr: &Range) -> VisitorTypes {
+        let mut bounds = Vec::new();
+        for bound in [&expr.start, &expr.end] {
+            match bound.accept(self) {
+                VisitorTypes::DataType(d) => bounds.push(whole_number(d.as_ref())),
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            }
+        }
+        match (bounds[0], bounds[1]) {
+            (Some(start), Some(end)) => {
+                let inclusive = expr.operator.token_type == TokenType::DotDotEqual;
+                VisitorTypes::DataType(Some(DataType::Range(LoxRange::new(start, end, inclusive))))
+            }
+            _ => {
+                let msg = "Range bounds must be whole numbers.";
+                self.visitor_runtime_error(Some(&expr.operator), msg)
+            }
+        }
+    }
+
     fn visit_set_expr(&mut self, expr: &Set) -> VisitorTypes {
-        let err_msg = "Only instances have fields.";
+        let err_msg = "Only instances and classes have fields.";
         let object = match expr.object.accept(self) {
             VisitorTypes::DataType(d) => d,
             _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
@@ -430,13 +1704,60 @@ impl ExprVisitor for Interpreter {
             VisitorTypes::DataType(d) => d,
             _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
         };
-        match object {
-            Some(DataType::Instance(instance)) => {
-                instance.set(&expr.name, value.clone());
+        let through_this = expr.object.as_any().downcast_ref::<This>().is_some();
+        self.set_property(object, &expr.name, through_this, value)
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> VisitorTypes {
+        let element = match self.evaluate_element(&expr.object, &expr.index, &expr.bracket) {
+            Ok(element) => element,
+            Err(e) => return *e,
+        };
+        let value = match expr.value.accept(self) {
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
             }
-            _ => return self.visitor_runtime_error(Some(&expr.name.dup()), err_msg),
-        }
-        VisitorTypes::DataType(value)
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        element.set(value.clone());
+        VisitorTypes::DataType(Some(value))
+    }
+
+    /// Slices are copies, changing the slice of a list doesn't change the list.
+    fn visit_slice_expr(&mut self, expr: &Slice) -> VisitorTypes {
+        let object = match expr.object.accept(self) {
+            VisitorTypes::DataType(_) if self.short_circuit => {
+                return VisitorTypes::DataType(Some(DataType::Nil));
+            }
+            VisitorTypes::DataType(d) => d,
+            VisitorTypes::RunTimeError { token, msg } => {
+                return VisitorTypes::RunTimeError { token, msg };
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let length = match &object {
+            Some(DataType::List(list)) => list.borrow().len(),
+            Some(DataType::String(s)) => s.chars().count(),
+            _ => {
+                let msg = "Only lists and strings can be sliced.";
+                return self.visitor_runtime_error(Some(&expr.bracket), msg);
+            }
+        };
+        let start = match self.evaluate_slice_bound(&expr.start, 0, length, &expr.bracket) {
+            Ok(start) => start,
+            Err(e) => return *e,
+        };
+        let end = match self.evaluate_slice_bound(&expr.end, length, length, &expr.bracket) {
+            Ok(end) => end,
+            Err(e) => return *e,
+        };
+        self.slice(object, start, end, &expr.bracket)
+    }
+
+    /// A spread is evaluated to its list, visit_call_expr passes the elements on as arguments.
+    fn visit_spread_expr(&mut self, expr: &Spread) -> VisitorTypes {
+        expr.expr.accept(self)
     }
 
     fn visit_super_expr(&mut self, expr: &Super) -> VisitorTypes {
@@ -451,29 +1772,94 @@ impl ExprVisitor for Interpreter {
 
     fn visit_unary_expr(&mut self, expr: &Unary) -> VisitorTypes {
         let right = match expr.right.accept(self) {
-            VisitorTypes::DataType(d) => match d {
-                Some(v) => match v {
-                    DataType::Number(v) => DataType::Number(-v),
-                    _ => {
-                        return self
-                            .visitor_runtime_error(Some(&expr.operator), "Expected a number.")
-                    }
-                },
-                None => DataType::Nil,
-            },
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
             _ => return self.visitor_runtime_error(Some(&expr.operator), "Expected a number."),
         };
-        match expr.operator.token_type {
-            TokenType::Minus => VisitorTypes::DataType(Some(right)),
-            TokenType::Bang => {
+        match (expr.operator.token_type, right) {
+            (TokenType::Minus, DataType::Integer(n)) => {
+                self.integer_result(n.checked_neg(), -(n as f64), &expr.operator)
+            }
+            (TokenType::Minus, DataType::Number(n)) => {
+                VisitorTypes::DataType(Some(DataType::Number(-n)))
+            }
+            (TokenType::Minus, DataType::BigInt(n)) => {
+                VisitorTypes::DataType(Some(DataType::BigInt(n.neg())))
+            }
+            (TokenType::Minus, DataType::Decimal(n)) => {
+                VisitorTypes::DataType(Some(DataType::Decimal(n.neg())))
+            }
+            (TokenType::Minus, _) => {
+                self.visitor_runtime_error(Some(&expr.operator), "Expected a number.")
+            }
+            (TokenType::Tilde, right) => match whole_number(Some(&right)) {
+                Some(n) => VisitorTypes::DataType(Some(DataType::Integer(!n))),
+                None => {
+                    let msg = bitwise_error(None, Some(&right));
+                    self.visitor_runtime_error(Some(&expr.operator), msg)
+                }
+            },
+            (TokenType::Bang, right) => {
                 VisitorTypes::DataType(Some(DataType::Bool(!self.is_truthy(&right))))
             }
             _ => {
-                self.visitor_runtime_error(Some(&expr.operator), "Expected a '!' or '-' operator.")
+                let msg = "Expected a '!', '-' or '~' operator.";
+                self.visitor_runtime_error(Some(&expr.operator), msg)
             }
         }
     }
 
+    fn visit_update_expr(&mut self, expr: &Update) -> VisitorTypes {
+        if let Some(variable) = expr.target.as_any().downcast_ref::<Variable>() {
+            // The resolver resolved the target as a Variable, that expression is used for reading and writing.
+            let target: Rc<dyn Expr> = Rc::new(Variable::new(variable.name.dup()));
+            let old = match self.lookup_variable(&variable.name, &target) {
+                VisitorTypes::DataType(d) => d,
+                error => return error,
+            };
+            return match self.updated_value(old, expr) {
+                Ok((new, result)) => match self.assign_variable(&variable.name, &target, new) {
+                    VisitorTypes::DataType(_) => VisitorTypes::DataType(Some(result)),
+                    error => error,
+                },
+                Err(e) => *e,
+            };
+        }
+        if let Some(get) = expr.target.as_any().downcast_ref::<Get>() {
+            let object = match get.object.accept(self) {
+                VisitorTypes::DataType(d) => d,
+                VisitorTypes::RunTimeError { token, msg } => {
+                    return VisitorTypes::RunTimeError { token, msg };
+                }
+                _ => panic!("Interpreter entered impossible state."),
+            };
+            let old = match self.get_property(object.clone(), get) {
+                VisitorTypes::DataType(d) => d,
+                error => return error,
+            };
+            let (new, result) = match self.updated_value(old, expr) {
+                Ok(values) => values,
+                Err(e) => return *e,
+            };
+            let through_this = get.object.as_any().downcast_ref::<This>().is_some();
+            return match self.set_property(object, &get.name, through_this, Some(new)) {
+                VisitorTypes::DataType(_) => VisitorTypes::DataType(Some(result)),
+                error => error,
+            };
+        }
+        // The parser only allows variables, fields and list or map elements as the target.
+        let index = expr.target.as_any().downcast_ref::<Index>().unwrap();
+        let element = match self.evaluate_element(&index.object, &index.index, &index.bracket) {
+            Ok(element) => element,
+            Err(e) => return *e,
+        };
+        let (new, result) = match self.updated_value(Some(element.get()), expr) {
+            Ok(values) => values,
+            Err(e) => return *e,
+        };
+        element.set(new);
+        VisitorTypes::DataType(Some(result))
+    }
+
     fn visit_variable_expr(&mut self, expr: &Variable) -> VisitorTypes {
         let name = expr.name.dup();
         let expr: Rc<dyn Expr> = Rc::new(Variable::new(name.dup()));
@@ -482,6 +1868,31 @@ impl ExprVisitor for Interpreter {
 }
 
 impl StmtVisitor for Interpreter {
+    fn visit_assert_stmt(&mut self, stmt: &Assert) -> VisitorTypes {
+        if !config::get_assertions() {
+            return VisitorTypes::Void(());
+        }
+        let condition = match stmt.condition.accept(self) {
+            VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+            error => return error,
+        };
+        if self.is_truthy(&condition) {
+            return VisitorTypes::Void(());
+        }
+        let msg = match &stmt.message {
+            Some(message) => match message.accept(self) {
+                VisitorTypes::DataType(d) => format!(
+                    "Assertion '{}' failed: {}",
+                    stmt.text,
+                    self.stringify_helper(d)
+                ),
+                error => return error,
+            },
+            None => format!("Assertion '{}' failed.", stmt.text),
+        };
+        self.visitor_runtime_error(Some(&stmt.keyword), &msg)
+    }
+
     fn visit_block_stmt(&mut self, stmt: &Block) -> VisitorTypes {
         let env = Environment::new_enclosing(self.environment.borrow().clone());
         // This might introduce bugs in the future.
@@ -489,6 +1900,9 @@ impl StmtVisitor for Interpreter {
     }
 
     fn visit_class_stmt(&mut self, stmt: &Class) -> VisitorTypes {
+        if let Err(e) = self.check_redeclaration(&stmt.name) {
+            return *e;
+        }
         self.environment
             .borrow()
             .borrow_mut()
@@ -507,7 +1921,83 @@ impl StmtVisitor for Interpreter {
             }
         }
 
-        let class = LoxClass::new(stmt.name.dup().lexeme, methods);
+        // Methods of included traits are copied into the class, unless the class defines them itself.
+        let mut trait_methods: HashMap<String, (String, LoxFunction)> = HashMap::new();
+        for trait_expr in stmt.traits.iter() {
+            let token = match trait_expr.as_any().downcast_ref::<Variable>() {
+                Some(v) => v.name.dup(),
+                None => stmt.name.dup(),
+            };
+            let lox_trait = match trait_expr.accept(self) {
+                VisitorTypes::DataType(Some(DataType::Trait(t))) => t,
+                _ => return self.visitor_runtime_error(Some(&token), "Can only include traits."),
+            };
+            for (name, method) in lox_trait.methods {
+                if methods.contains_key(&name) {
+                    continue;
+                }
+                if let Some((other, _)) = trait_methods.get(&name) {
+                    let msg = format!(
+                        "Method '{}' is defined by both trait '{}' and trait '{}'.",
+                        name, other, lox_trait.name
+                    );
+                    return self.visitor_runtime_error(Some(&token), &msg);
+                }
+                trait_methods.insert(name, (lox_trait.name.clone(), method));
+            }
+        }
+        for (name, (_, method)) in trait_methods {
+            methods.insert(name, method);
+        }
+
+        let mut getters: HashMap<String, LoxFunction> = HashMap::new();
+        for getter in stmt.getters.iter() {
+            if let Some(f) = getter.as_any().downcast_ref::<Function>() {
+                let function = LoxFunction::new(f, &self.environment.borrow(), false);
+                getters.insert(f.name.dup().lexeme, function);
+            }
+        }
+
+        let mut setters: HashMap<String, LoxFunction> = HashMap::new();
+        for setter in stmt.setters.iter() {
+            if let Some(f) = setter.as_any().downcast_ref::<Function>() {
+                let function = LoxFunction::new(f, &self.environment.borrow(), false);
+                setters.insert(f.name.dup().lexeme, function);
+            }
+        }
+
+        let mut static_methods: HashMap<String, LoxFunction> = HashMap::new();
+        for method in stmt.static_methods.iter() {
+            if let Some(f) = method.as_any().downcast_ref::<Function>() {
+                let function = LoxFunction::new(f, &self.environment.borrow(), false);
+                static_methods.insert(f.name.dup().lexeme, function);
+            }
+        }
+
+        let mut fields: HashMap<String, DataType> = HashMap::new();
+        for field in stmt.static_fields.iter() {
+            if let Some(var) = field.as_any().downcast_ref::<Var>() {
+                // The parser only allows a single name for a class-level variable.
+                let name = var.pattern.token();
+                let value = match &var.initializer {
+                    Some(initializer) => match initializer.accept(self) {
+                        VisitorTypes::DataType(d) => d.unwrap_or(DataType::Nil),
+                        _ => return self.visitor_runtime_error(Some(name), "Expected a value."),
+                    },
+                    None => DataType::Nil,
+                };
+                fields.insert(name.dup().lexeme, value);
+            }
+        }
+
+        let class = LoxClass::new(
+            stmt.name.dup().lexeme,
+            methods,
+            getters,
+            setters,
+            static_methods,
+            fields,
+        );
         self.environment
             .borrow()
             .borrow_mut()
@@ -515,6 +2005,11 @@ impl StmtVisitor for Interpreter {
         VisitorTypes::Void(())
     }
 
+    /// A block takes out its defers before it runs its statements, one that reaches the interpreter isn't directly in a block.
+    fn visit_defer_stmt(&mut self, stmt: &Defer) -> VisitorTypes {
+        self.visitor_runtime_error(Some(&stmt.keyword), "Can only defer in a block.")
+    }
+
     fn visit_expression_stmt(&mut self, stmt: &Expression) -> VisitorTypes {
         let expr = stmt.expression.accept(self);
         if self.is_repl && self.is_last_statement {
@@ -523,7 +2018,37 @@ impl StmtVisitor for Interpreter {
         VisitorTypes::Void(())
     }
 
+    /// Lists and maps are iterated over as they were when the loop started,
+    /// changing them in the body doesn't change what the loop goes over. Maps give their keys.
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> VisitorTypes {
+        let iterable = match stmt.iterable.accept(self) {
+            VisitorTypes::DataType(d) => d,
+            error @ VisitorTypes::RunTimeError { .. } => {
+                return self.iteration_error(error, &stmt.keyword);
+            }
+            _ => panic!("Interpreter entered impossible state."),
+        };
+        let mut iteration = match self.iteration(iterable, &stmt.keyword) {
+            Ok(iteration) => iteration,
+            Err(e) => return *e,
+        };
+        loop {
+            match self.next_iteration(&mut iteration, &stmt.keyword) {
+                Ok(Some(value)) => {
+                    if let VisitorTypes::Return(r) = self.run_iteration(stmt, value) {
+                        return VisitorTypes::Return(r);
+                    }
+                }
+                Ok(None) => return VisitorTypes::Void(()),
+                Err(e) => return *e,
+            }
+        }
+    }
+
     fn visit_function_stmt(&mut self, stmt: &Function) -> VisitorTypes {
+        if let Err(e) = self.check_redeclaration(&stmt.name) {
+            return *e;
+        }
         let function = LoxFunction::new(stmt, &self.environment.borrow(), false);
         self.environment
             .borrow()
@@ -534,9 +2059,9 @@ impl StmtVisitor for Interpreter {
 
     fn visit_if_stmt(&mut self, stmt: &If) -> VisitorTypes {
         let mut potential_return: VisitorTypes = VisitorTypes::Void(());
-        let condition = match stmt.condition.accept(self) {
-            VisitorTypes::DataType(Some(d)) => d,
-            _ => return self.visitor_runtime_error(None, "Expected a condition."),
+        let condition = match self.condition_in(&stmt.condition, self.environment()) {
+            Ok(condition) => condition,
+            Err(e) => return *e,
         };
         if self.is_truthy(&condition) {
             potential_return = self.execute(&stmt.then_branch);
@@ -546,6 +2071,20 @@ impl StmtVisitor for Interpreter {
         potential_return
     }
 
+    /// Arms are tried in order, only the first one that matches runs.
+    fn visit_match_stmt(&mut self, stmt: &Match) -> VisitorTypes {
+        let (body, environment) = match self.select_arm(stmt, self.environment()) {
+            Ok(Some(arm)) => arm,
+            Ok(None) => return VisitorTypes::Void(()),
+            Err(e) => return *e,
+        };
+        match self.execute_in(&body, environment) {
+            VisitorTypes::Return(r) => VisitorTypes::Return(r),
+            VisitorTypes::RunTimeError { token, msg } => VisitorTypes::RunTimeError { token, msg },
+            _ => VisitorTypes::Void(()),
+        }
+    }
+
     fn visit_print_stmt(&mut self, stmt: &Print) -> VisitorTypes {
         let value = stmt.expression.accept(self);
         if let Ok(s) = self.stringify(value) {
@@ -570,31 +2109,75 @@ impl StmtVisitor for Interpreter {
         }
     }
 
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> VisitorTypes {
+        if let Err(e) = self.check_redeclaration(&stmt.name) {
+            return *e;
+        }
+        let mut methods: HashMap<String, LoxFunction> = HashMap::new();
+        for method in stmt.methods.iter() {
+            if let Some(f) = method.as_any().downcast_ref::<Function>() {
+                let is_init = f.name.lexeme == "init";
+                let function = LoxFunction::new(f, &self.environment.borrow(), is_init);
+                methods.insert(f.name.dup().lexeme, function);
+            }
+        }
+        let lox_trait = LoxTrait::new(stmt.name.dup().lexeme, methods);
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .define(stmt.name.dup().lexeme, DataType::Trait(lox_trait));
+        VisitorTypes::Void(())
+    }
+
     fn visit_var_stmt(&mut self, stmt: &Var) -> VisitorTypes {
         let mut data_type = None;
         if let Some(initializer) = &stmt.initializer {
             data_type = match initializer.accept(self) {
                 VisitorTypes::DataType(d) => d,
-                _ => return self.visitor_runtime_error(Some(&stmt.name), "Expected a value."),
+                _ => {
+                    return self
+                        .visitor_runtime_error(Some(stmt.pattern.token()), "Expected a value.")
+                }
             }
         }
-        let value = match data_type {
-            Some(v) => v,
-            None => DataType::Nil,
+        let (names, values) = match &stmt.pattern {
+            BindingPattern::Name(name) => (vec![name], vec![data_type.unwrap_or(DataType::Nil)]),
+            BindingPattern::List { bracket, names } => {
+                match self.list_parts(data_type, names.len(), bracket) {
+                    Ok(parts) => (names.iter().collect(), parts),
+                    Err(e) => return *e,
+                }
+            }
+            BindingPattern::Map { brace, names } => {
+                match self.field_parts(data_type, names, brace) {
+                    Ok(parts) => (names.iter().collect(), parts),
+                    Err(e) => return *e,
+                }
+            }
         };
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(stmt.name.dup().lexeme, value);
+        for name in names.iter() {
+            if let Err(e) = self.check_redeclaration(name) {
+                return *e;
+            }
+        }
+        for (name, value) in names.into_iter().zip(values) {
+            let environment = self.environment.borrow();
+            let mut environment = environment.borrow_mut();
+            if stmt.constant {
+                environment.define_constant(name.dup().lexeme, value);
+            } else {
+                environment.define(name.dup().lexeme, value);
+            }
+        }
         VisitorTypes::Void(())
     }
 
     fn visit_while_stmt(&mut self, stmt: &While) -> VisitorTypes {
         let mut condition_valid = true;
         while condition_valid {
-            let condition = match stmt.condition.accept(self) {
-                VisitorTypes::DataType(Some(d)) => d,
-                _ => return self.visitor_runtime_error(None, "Expected a condition."),
+            let condition = match self.condition_in(&stmt.condition, self.environment()) {
+                Ok(condition) => condition,
+                Err(e) => return *e,
             };
             if self.is_truthy(&condition) {
                 if let VisitorTypes::Return(v) = self.execute(&stmt.body) {
@@ -606,4 +2189,164 @@ impl StmtVisitor for Interpreter {
         }
         VisitorTypes::Void(())
     }
+
+    /// A generator runs the yields in its body itself, one that reaches the interpreter isn't directly in a generator body.
+    fn visit_yield_stmt(&mut self, stmt: &Yield) -> VisitorTypes {
+        self.visitor_runtime_error(Some(&stmt.keyword), "Can only yield in a generator body.")
+    }
+}
+
+/// The body of the match arm that runs, with the environment that holds the name its pattern binds.
+pub type ArmBody = (Rc<dyn Stmt>, Rc<RefCell<Environment>>);
+
+/// The values a for-in loop goes over, they are made one at a time.
+pub enum Iteration {
+    Values(Box<dyn Iterator<Item = DataType>>),
+    // The iterator of an object and its next() method.
+    Object(Rc<LoxInstance>, LoxFunction),
+    Generator(LoxGenerator),
+}
+
+/// An assignable element of a list or a map.
+enum Element {
+    List(LoxList, usize),
+    Map(LoxMap, String),
+}
+impl Element {
+    /// A key that isn't in the map gives nil, like reading it with an index expression does.
+    fn get(&self) -> DataType {
+        match self {
+            Element::List(list, index) => list.borrow()[*index].clone(),
+            Element::Map(map, key) => map.borrow().get(key).cloned().unwrap_or(DataType::Nil),
+        }
+    }
+
+    fn set(&self, value: DataType) {
+        match self {
+            Element::List(list, index) => list.borrow_mut()[*index] = value,
+            Element::Map(map, key) => {
+                map.borrow_mut().insert(key.clone(), value);
+            }
+        }
+    }
+}
+
+/// The operands of an arithmetic operation or comparison.
+/// They only stay integers when both sides are integers, otherwise both become floats.
+/// Exact numbers are widened to the widest exact kind of the two: integer, big integer, decimal.
+enum Operands {
+    Integer(i64, i64),
+    Number(f64, f64),
+    BigInt(BigInt, BigInt),
+    Decimal(Decimal, Decimal),
+}
+impl Operands {
+    fn new(left: Option<&DataType>, right: Option<&DataType>) -> Option<Operands> {
+        match (left?, right?) {
+            (DataType::Integer(l), DataType::Integer(r)) => Some(Operands::Integer(*l, *r)),
+            (DataType::Integer(l), DataType::Number(r)) => Some(Operands::Number(*l as f64, *r)),
+            (DataType::Number(l), DataType::Integer(r)) => Some(Operands::Number(*l, *r as f64)),
+            (DataType::Number(l), DataType::Number(r)) => Some(Operands::Number(*l, *r)),
+            (l @ DataType::Decimal(_), r) | (l, r @ DataType::Decimal(_)) => {
+                Some(Operands::Decimal(exact_decimal(l)?, exact_decimal(r)?))
+            }
+            (l, r) => Some(Operands::BigInt(exact_bigint(l)?, exact_bigint(r)?)),
+        }
+    }
+
+    /// Exact numbers never silently turn into floats, mixing them is an error instead.
+    fn mixes_exact_and_float(left: Option<&DataType>, right: Option<&DataType>) -> bool {
+        let is_exact = |d: Option<&DataType>| {
+            matches!(d, Some(DataType::BigInt(_)) | Some(DataType::Decimal(_)))
+        };
+        let is_float = |d: Option<&DataType>| matches!(d, Some(DataType::Number(_)));
+        (is_exact(left) && is_float(right)) || (is_float(left) && is_exact(right))
+    }
+
+    fn as_numbers(&self) -> (f64, f64) {
+        match self {
+            Operands::Integer(l, r) => (*l as f64, *r as f64),
+            Operands::Number(l, r) => (*l, *r),
+            Operands::BigInt(l, r) => (l.to_f64(), r.to_f64()),
+            Operands::Decimal(l, r) => (l.to_f64(), r.to_f64()),
+        }
+    }
+
+    fn has_zero_divisor(&self) -> bool {
+        match self {
+            Operands::Integer(_, r) => *r == 0,
+            Operands::Number(_, r) => *r == 0.0,
+            Operands::BigInt(_, r) => r.is_zero(),
+            Operands::Decimal(_, r) => r.is_zero(),
+        }
+    }
+
+    /// Integers are compared exactly, so large values that map to the same float are still ordered.
+    fn compare(&self) -> Option<Ordering> {
+        match self {
+            Operands::Integer(l, r) => Some(l.cmp(r)),
+            Operands::Number(l, r) => l.partial_cmp(r),
+            Operands::BigInt(l, r) => Some(l.cmp(r)),
+            Operands::Decimal(l, r) => Some(l.cmp(r)),
+        }
+    }
+}
+
+fn exact_bigint(data_type: &DataType) -> Option<BigInt> {
+    match data_type {
+        DataType::Integer(n) => Some(BigInt::from(*n)),
+        DataType::BigInt(n) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+fn exact_decimal(data_type: &DataType) -> Option<Decimal> {
+    match data_type {
+        DataType::Decimal(n) => Some(n.clone()),
+        _ => exact_bigint(data_type).map(Decimal::from),
+    }
+}
+
+/// The integer value of an operand of a bitwise operator or a bound of a range.
+fn whole_number(data_type: Option<&DataType>) -> Option<i64> {
+    match data_type? {
+        DataType::Integer(n) => Some(*n),
+        // 2^63 itself doesn't fit, so the upper bound is exclusive.
+        DataType::Number(n) if n.fract() == 0.0 && *n >= -(2f64.powi(63)) && *n < 2f64.powi(63) => {
+            Some(*n as i64)
+        }
+        _ => None,
+    }
+}
+
+/// The error for operands of a bitwise operator that whole_number doesn't take.
+/// Big integers and decimals have no fixed amount of bits, so they are not supported even when they are whole.
+fn bitwise_error(left: Option<&DataType>, right: Option<&DataType>) -> &'static str {
+    let is_exact =
+        |d: Option<&DataType>| matches!(d, Some(DataType::BigInt(_)) | Some(DataType::Decimal(_)));
+    if is_exact(left) || is_exact(right) {
+        "Bitwise operators are not supported on big integers and decimals."
+    } else {
+        "Bitwise operators only work on whole numbers."
+    }
+}
+
+/// Integer division rounding towards negative infinity, None when it overflows.
+fn floor_div(l: i64, r: i64) -> Option<i64> {
+    let quotient = l.checked_div(r)?;
+    if l.wrapping_rem(r) != 0 && (l < 0) != (r < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+/// Integer remainder with the sign of the divisor.
+fn floor_mod(l: i64, r: i64) -> i64 {
+    let remainder = l.wrapping_rem(r);
+    if remainder != 0 && (remainder < 0) != (r < 0) {
+        remainder + r
+    } else {
+        remainder
+    }
 }
@@ -4,13 +4,18 @@ use colored::Colorize;
 
 mod ast;
 mod ast_printer;
+mod bignum;
 mod class;
+mod config;
 mod environment;
 mod error;
+mod fiber;
 mod function;
+mod generator;
 mod interpreter;
 mod native_functions;
 mod parser;
+mod range;
 mod repl;
 mod resolver;
 mod run;
@@ -21,7 +26,14 @@ mod token;
 extern crate lazy_static;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    for flag in flags {
+        if !config::apply_flag(&flag) {
+            println!("Unknown flag: {flag}");
+            process::exit(64);
+        }
+    }
     match args.len() {
         2 => match source::run_file(args[1].as_str()) {
             Ok(_) => {}
@@ -32,7 +44,7 @@ fn main() {
         },
         1 | 0 => repl::prompt(),
         _ => {
-            println!("Usage: jlox [script]");
+            println!("Usage: jlox [--int-overflow=promote|error] [--no-assert] [script]");
             process::exit(64);
         }
     }
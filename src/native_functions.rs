@@ -1,9 +1,21 @@
 use std::{
+    cell::RefCell,
+    convert::TryFrom,
     fmt::{self, Display, Formatter},
+    rc::Rc,
     time::SystemTime,
 };
 
-use crate::{function::LoxCallable, interpreter::Interpreter, token::DataType};
+use crate::{
+    ast::VisitorTypes,
+    bignum::{BigInt, Decimal},
+    fiber::{run_scheduler, LoxFiber},
+    function::{LoxCallable, LoxNative},
+    generator::LoxGenerator,
+    interpreter::Interpreter,
+    range::LoxRange,
+    token::{DataType, Token, TokenType},
+};
 #[derive(Debug)]
 pub struct Clock {
     name: String,
@@ -14,11 +26,12 @@ impl Clock {
     }
 }
 impl LoxCallable for Clock {
-    fn call(&self, _: &mut Interpreter, _: Vec<crate::token::DataType>) -> DataType {
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+    fn call(&self, _: &mut Interpreter, _: Vec<crate::token::DataType>) -> VisitorTypes {
+        let time = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
             Ok(n) => DataType::Number(n.as_millis() as f64),
             Err(_) => DataType::Nil,
-        }
+        };
+        VisitorTypes::DataType(Some(time))
     }
 
     fn arity(&self) -> usize {
@@ -42,9 +55,9 @@ impl Println {
 }
 
 impl LoxCallable for Println {
-    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> DataType {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
         println!("{}", arguments[0]);
-        DataType::Nil
+        VisitorTypes::DataType(Some(DataType::Nil))
     }
 
     fn arity(&self) -> usize {
@@ -57,3 +70,797 @@ impl Display for Println {
         write!(f, "<Native-Function {}>", self.name)
     }
 }
+
+/// Runtime errors raised by natives carry no token, the interpreter reports them at the call.
+fn native_error(msg: &str) -> VisitorTypes {
+    VisitorTypes::RunTimeError {
+        token: None,
+        msg: msg.to_string(),
+    }
+}
+
+/// Turns a list of names into a Lox list of strings.
+fn string_list(names: Vec<String>) -> VisitorTypes {
+    let list = names.into_iter().map(DataType::String).collect();
+    VisitorTypes::DataType(Some(DataType::List(Rc::new(RefCell::new(list)))))
+}
+
+/// Drops the token of an error raised by a property lookup, the property name only exists at runtime.
+fn without_token(result: VisitorTypes) -> VisitorTypes {
+    match result {
+        VisitorTypes::RunTimeError { msg, .. } => VisitorTypes::RunTimeError { token: None, msg },
+        result => result,
+    }
+}
+
+/// Builds the token a property lookup needs from a name given at runtime.
+fn property_token(name: &DataType) -> Option<Token> {
+    match name {
+        DataType::String(s) => Some(Token::new(TokenType::Identifier, s.clone(), None, 0, 0, 0)),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct Len {
+    name: String,
+}
+impl Len {
+    pub fn new(name: String) -> Len {
+        Len { name }
+    }
+}
+
+impl LoxCallable for Len {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match &arguments[0] {
+            DataType::List(l) => {
+                VisitorTypes::DataType(Some(DataType::Integer(l.borrow().len() as i64)))
+            }
+            DataType::Map(m) => {
+                VisitorTypes::DataType(Some(DataType::Integer(m.borrow().len() as i64)))
+            }
+            // The amount of values a range has, they don't have to be made for this.
+            DataType::Range(r) => match i64::try_from(r.count()) {
+                Ok(count) => VisitorTypes::DataType(Some(DataType::Integer(count))),
+                Err(_) => VisitorTypes::DataType(Some(DataType::Number(r.count() as f64))),
+            },
+            // The length of a string is its amount of characters, not bytes.
+            DataType::String(s) => {
+                VisitorTypes::DataType(Some(DataType::Integer(s.chars().count() as i64)))
+            }
+            _ => native_error("Can only get the length of a list, map, range or string."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for Len {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct TypeOf {
+    name: String,
+}
+impl TypeOf {
+    pub fn new(name: String) -> TypeOf {
+        TypeOf { name }
+    }
+}
+
+impl LoxCallable for TypeOf {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let type_name = match &arguments[0] {
+            DataType::String(_) => "string",
+            DataType::Number(_) | DataType::Integer(_) => "number",
+            DataType::BigInt(_) => "bigint",
+            DataType::Decimal(_) => "decimal",
+            DataType::Bool(_) => "bool",
+            DataType::Nil => "nil",
+            DataType::Function(_) | DataType::Native(_) => "function",
+            DataType::Class(_) => "class",
+            DataType::Trait(_) => "trait",
+            DataType::Instance(_) => "instance",
+            DataType::List(_) => "list",
+            DataType::Map(_) => "map",
+            DataType::Range(_) => "range",
+            DataType::Generator(_) => "generator",
+            DataType::Fiber(_) => "fiber",
+        };
+        VisitorTypes::DataType(Some(DataType::String(type_name.to_string())))
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for TypeOf {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassOf {
+    name: String,
+}
+impl ClassOf {
+    pub fn new(name: String) -> ClassOf {
+        ClassOf { name }
+    }
+}
+
+impl LoxCallable for ClassOf {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match &arguments[0] {
+            DataType::Instance(i) => {
+                VisitorTypes::DataType(Some(DataType::Class(i.class().clone())))
+            }
+            _ => native_error("Can only get the class of an instance."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for ClassOf {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassName {
+    name: String,
+}
+impl ClassName {
+    pub fn new(name: String) -> ClassName {
+        ClassName { name }
+    }
+}
+
+impl LoxCallable for ClassName {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match &arguments[0] {
+            DataType::Class(c) => {
+                VisitorTypes::DataType(Some(DataType::String(c.name().to_string())))
+            }
+            _ => native_error("Can only get the name of a class."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for ClassName {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Lists the public fields of an instance, or the class-level variables of a class.
+#[derive(Debug)]
+pub struct Fields {
+    name: String,
+}
+impl Fields {
+    pub fn new(name: String) -> Fields {
+        Fields { name }
+    }
+}
+
+impl LoxCallable for Fields {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match &arguments[0] {
+            DataType::Instance(i) => string_list(i.field_names()),
+            DataType::Class(c) => string_list(c.field_names()),
+            _ => native_error("Only instances and classes have fields."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for Fields {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Lists the public methods of a class, instances are looked up through their class.
+#[derive(Debug)]
+pub struct Methods {
+    name: String,
+}
+impl Methods {
+    pub fn new(name: String) -> Methods {
+        Methods { name }
+    }
+}
+
+impl LoxCallable for Methods {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match &arguments[0] {
+            DataType::Class(c) => string_list(c.method_names()),
+            DataType::Instance(i) => string_list(i.class().method_names()),
+            _ => native_error("Only instances and classes have methods."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for Methods {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Checks if a field is stored on the instance, getters and methods don't count.
+#[derive(Debug)]
+pub struct HasField {
+    name: String,
+}
+impl HasField {
+    pub fn new(name: String) -> HasField {
+        HasField { name }
+    }
+}
+
+impl LoxCallable for HasField {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let token = match property_token(&arguments[1]) {
+            Some(token) => token,
+            None => return native_error("Property name must be a string."),
+        };
+        match &arguments[0] {
+            DataType::Instance(i) => {
+                VisitorTypes::DataType(Some(DataType::Bool(i.has_field(&token.lexeme))))
+            }
+            _ => native_error("Only instances have fields."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+impl Display for HasField {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Reads a property by name, the same way 'obj.name' does from outside the class.
+#[derive(Debug)]
+pub struct GetField {
+    name: String,
+}
+impl GetField {
+    pub fn new(name: String) -> GetField {
+        GetField { name }
+    }
+}
+
+impl LoxCallable for GetField {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let token = match property_token(&arguments[1]) {
+            Some(token) => token,
+            None => return native_error("Property name must be a string."),
+        };
+        match &arguments[0] {
+            DataType::Instance(i) => without_token(i.get(&token, false, interpreter)),
+            DataType::Class(c) => without_token(c.get(&token)),
+            _ => native_error("Only instances and classes have properties."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+impl Display for GetField {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Writes a property by name, the same way 'obj.name = value' does from outside the class.
+#[derive(Debug)]
+pub struct SetField {
+    name: String,
+}
+impl SetField {
+    pub fn new(name: String) -> SetField {
+        SetField { name }
+    }
+}
+
+impl LoxCallable for SetField {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let token = match property_token(&arguments[1]) {
+            Some(token) => token,
+            None => return native_error("Property name must be a string."),
+        };
+        let value = arguments[2].clone();
+        match &arguments[0] {
+            DataType::Instance(i) => {
+                if let VisitorTypes::RunTimeError { msg, .. } =
+                    i.set(&token, Some(value.clone()), false, interpreter)
+                {
+                    return native_error(&msg);
+                }
+            }
+            DataType::Class(c) => c.set(&token, Some(value.clone())),
+            _ => return native_error("Only instances and classes have fields."),
+        }
+        VisitorTypes::DataType(Some(value))
+    }
+
+    fn arity(&self) -> usize {
+        3
+    }
+}
+
+impl Display for SetField {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Converts a whole number or a string of digits to a big integer.
+#[derive(Debug)]
+pub struct ToBigInt {
+    name: String,
+}
+impl ToBigInt {
+    pub fn new(name: String) -> ToBigInt {
+        ToBigInt { name }
+    }
+}
+
+impl LoxCallable for ToBigInt {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let value = match &arguments[0] {
+            DataType::Integer(n) => Some(BigInt::from(*n)),
+            DataType::Number(n) => BigInt::from_f64(*n),
+            DataType::BigInt(n) => Some(n.clone()),
+            DataType::Decimal(n) => n.to_bigint(),
+            DataType::String(s) => BigInt::parse(s.trim()),
+            _ => None,
+        };
+        match value {
+            Some(n) => VisitorTypes::DataType(Some(DataType::BigInt(n))),
+            None => native_error("Can only convert whole numbers to a big integer."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for ToBigInt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Converts a number or a numeric string to a decimal, floats are converted through their shortest representation.
+#[derive(Debug)]
+pub struct ToDecimal {
+    name: String,
+}
+impl ToDecimal {
+    pub fn new(name: String) -> ToDecimal {
+        ToDecimal { name }
+    }
+}
+
+impl LoxCallable for ToDecimal {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let value = match &arguments[0] {
+            DataType::Integer(n) => Some(Decimal::from(BigInt::from(*n))),
+            DataType::Number(n) => Decimal::from_f64(*n),
+            DataType::BigInt(n) => Some(Decimal::from(n.clone())),
+            DataType::Decimal(n) => Some(n.clone()),
+            DataType::String(s) => Decimal::parse(s.trim()),
+            _ => None,
+        };
+        match value {
+            Some(n) => VisitorTypes::DataType(Some(DataType::Decimal(n))),
+            None => native_error("Can only convert finite numbers to a decimal."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for ToDecimal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// Converts any number or a numeric string to a float, exact numbers are rounded to the nearest float.
+#[derive(Debug)]
+pub struct ToNumber {
+    name: String,
+}
+impl ToNumber {
+    pub fn new(name: String) -> ToNumber {
+        ToNumber { name }
+    }
+}
+
+impl LoxCallable for ToNumber {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let value = match &arguments[0] {
+            DataType::Integer(n) => Some(*n as f64),
+            DataType::Number(n) => Some(*n),
+            DataType::BigInt(n) => Some(n.to_f64()),
+            DataType::Decimal(n) => Some(n.to_f64()),
+            DataType::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        };
+        match value {
+            Some(n) => VisitorTypes::DataType(Some(DataType::Number(n))),
+            None => native_error("Can only convert numbers to a number."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for ToNumber {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// A method of a string, bound to the string it was looked up on: "a,b".split(",").
+#[derive(Debug)]
+pub struct StringMethod {
+    name: String,
+    receiver: String,
+}
+impl StringMethod {
+    /// Gives back None if strings have no method with this name.
+    pub fn new(name: &str, receiver: String) -> Option<StringMethod> {
+        match name {
+            "upper" | "lower" | "trim" | "split" | "contains" | "startsWith" | "endsWith"
+            | "replace" => Some(StringMethod {
+                name: name.to_string(),
+                receiver,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl LoxCallable for StringMethod {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let mut strings = Vec::new();
+        for argument in &arguments {
+            match argument {
+                DataType::String(s) => strings.push(s.as_str()),
+                _ => return native_error(&format!("'{}' expects string arguments.", self.name)),
+            }
+        }
+        let s = self.receiver.as_str();
+        let result = match (self.name.as_str(), strings.as_slice()) {
+            ("upper", []) => DataType::String(s.to_uppercase()),
+            ("lower", []) => DataType::String(s.to_lowercase()),
+            ("trim", []) => DataType::String(s.trim().to_string()),
+            // An empty separator splits the string into its characters.
+            ("split", [""]) => return string_list(s.chars().map(|c| c.to_string()).collect()),
+            ("split", [separator]) => {
+                return string_list(s.split(separator).map(|part| part.to_string()).collect())
+            }
+            ("contains", [part]) => DataType::Bool(s.contains(part)),
+            ("startsWith", [prefix]) => DataType::Bool(s.starts_with(prefix)),
+            ("endsWith", [suffix]) => DataType::Bool(s.ends_with(suffix)),
+            ("replace", [from, to]) => DataType::String(s.replace(from, to)),
+            _ => panic!("String method called with the wrong amount of arguments."),
+        };
+        VisitorTypes::DataType(Some(result))
+    }
+
+    fn arity(&self) -> usize {
+        match self.name.as_str() {
+            "upper" | "lower" | "trim" => 0,
+            "replace" => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl Display for StringMethod {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// The stepBy method of a range, bound to the range it was looked up on: (0..10).stepBy(2).
+#[derive(Debug)]
+pub struct StepBy {
+    receiver: LoxRange,
+}
+impl StepBy {
+    pub fn new(receiver: LoxRange) -> StepBy {
+        StepBy { receiver }
+    }
+}
+
+impl LoxCallable for StepBy {
+    fn call(&self, _: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let step = match &arguments[0] {
+            DataType::Integer(n) => Some(*n),
+            DataType::Number(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        };
+        match step.and_then(|step| self.receiver.step_by(step)) {
+            Some(range) => VisitorTypes::DataType(Some(DataType::Range(range))),
+            None => native_error("The step of a range must be a whole number that is not zero."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for StepBy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function stepBy>")
+    }
+}
+
+/// The next method of a generator, it runs the generator up to its next yield.
+/// Gives the yielded value, or nil once the generator is done. isDone tells a yielded nil apart from the end.
+#[derive(Debug)]
+pub struct GeneratorNext {
+    receiver: LoxGenerator,
+}
+impl GeneratorNext {
+    pub fn new(receiver: LoxGenerator) -> GeneratorNext {
+        GeneratorNext { receiver }
+    }
+}
+
+impl LoxCallable for GeneratorNext {
+    fn call(&self, interpreter: &mut Interpreter, _: Vec<DataType>) -> VisitorTypes {
+        match self.receiver.resume(interpreter) {
+            Ok(value) => VisitorTypes::DataType(Some(value.unwrap_or(DataType::Nil))),
+            Err(e) => *e,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+}
+
+impl Display for GeneratorNext {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function next>")
+    }
+}
+
+/// Fiber(fn) makes a fiber that runs the function, Fiber.yield and Fiber.spawn are properties of it.
+#[derive(Debug)]
+pub struct FiberNew {
+    name: String,
+}
+impl FiberNew {
+    pub fn new(name: String) -> FiberNew {
+        FiberNew { name }
+    }
+}
+
+impl LoxCallable for FiberNew {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match new_fiber(interpreter, &arguments[0]) {
+            Ok(fiber) => VisitorTypes::DataType(Some(DataType::Fiber(fiber))),
+            Err(e) => *e,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn property(&self, name: &str) -> Option<DataType> {
+        let function: Rc<dyn LoxCallable> = match name {
+            "yield" => Rc::new(FiberYield),
+            "spawn" => Rc::new(FiberSpawn),
+            _ => return None,
+        };
+        Some(DataType::Native(LoxNative { function }))
+    }
+}
+
+impl Display for FiberNew {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
+
+/// A fiber runs a function or a class, the function gets the value of the first resume() as its argument.
+fn new_fiber(
+    interpreter: &Interpreter,
+    function: &DataType,
+) -> Result<LoxFiber, Box<VisitorTypes>> {
+    let function: Rc<dyn LoxCallable> = match function {
+        DataType::Function(f) => Rc::new(f.clone()),
+        DataType::Native(n) => Rc::clone(&n.function),
+        DataType::Class(c) => Rc::new(c.clone()),
+        _ => return Err(Box::new(native_error("A fiber needs a function to run."))),
+    };
+    if function.min_arity() > 1 {
+        return Err(Box::new(native_error(
+            "The function of a fiber can have at most one parameter without a default.",
+        )));
+    }
+    LoxFiber::new(function, interpreter)
+}
+
+/// Fiber.yield(v) stops the running fiber, the resume() that ran it gives v.
+/// Gives the value of the resume() that runs the fiber again.
+#[derive(Debug)]
+pub struct FiberYield;
+
+impl LoxCallable for FiberYield {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        // The scheduler can't stay borrowed while the fiber is stopped.
+        let current = interpreter.scheduler.borrow().current();
+        match current {
+            Some(fiber) => {
+                let value = fiber.yield_value(arguments[0].clone());
+                VisitorTypes::DataType(Some(value))
+            }
+            None => native_error("Can only yield inside of a fiber."),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn min_arity(&self) -> usize {
+        0
+    }
+}
+
+impl Display for FiberYield {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function yield>")
+    }
+}
+
+/// Fiber.spawn(fn) makes a fiber and hands it to the scheduler, it starts once the running code sleeps or is done.
+#[derive(Debug)]
+pub struct FiberSpawn;
+
+impl LoxCallable for FiberSpawn {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match new_fiber(interpreter, &arguments[0]) {
+            Ok(fiber) => {
+                interpreter.scheduler.borrow_mut().spawn(fiber.clone());
+                VisitorTypes::DataType(Some(DataType::Fiber(fiber)))
+            }
+            Err(e) => *e,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for FiberSpawn {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function spawn>")
+    }
+}
+
+/// The resume method of a fiber, it runs the fiber up to its next yield.
+/// Gives the yielded value, or the value the function returns once it is done.
+#[derive(Debug)]
+pub struct FiberResume {
+    receiver: LoxFiber,
+}
+impl FiberResume {
+    pub fn new(receiver: LoxFiber) -> FiberResume {
+        FiberResume { receiver }
+    }
+}
+
+impl LoxCallable for FiberResume {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        match self.receiver.resume(interpreter, arguments[0].clone()) {
+            Ok(value) => VisitorTypes::DataType(Some(value)),
+            Err(e) => *e,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn min_arity(&self) -> usize {
+        0
+    }
+}
+
+impl Display for FiberResume {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function resume>")
+    }
+}
+
+/// sleep(ms) in a fiber hands the fiber to the scheduler, which wakes it after the milliseconds.
+/// Outside of a fiber it runs the fibers that wake up in the meantime.
+#[derive(Debug)]
+pub struct Sleep {
+    name: String,
+}
+impl Sleep {
+    pub fn new(name: String) -> Sleep {
+        Sleep { name }
+    }
+}
+
+impl LoxCallable for Sleep {
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<DataType>) -> VisitorTypes {
+        let ms = match &arguments[0] {
+            DataType::Integer(n) if *n >= 0 => *n as u64,
+            DataType::Number(n) if *n >= 0.0 => *n as u64,
+            _ => {
+                return native_error(
+                    "Can only sleep for a number of milliseconds that isn't negative.",
+                )
+            }
+        };
+        let current = interpreter.scheduler.borrow().current();
+        match current {
+            Some(fiber) => fiber.sleep(ms),
+            None => {
+                let until = interpreter.scheduler.borrow().time().saturating_add(ms);
+                run_scheduler(interpreter, Some(until));
+            }
+        }
+        VisitorTypes::DataType(Some(DataType::Nil))
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl Display for Sleep {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "<Native-Function {}>", self.name)
+    }
+}
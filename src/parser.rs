@@ -5,21 +5,32 @@ use std::{
 
 use crate::{
     ast::{
-        Assign, Binary, Block, Call, Class, Expr, Expression, Function, Get, Grouping, If, Literal,
-        Logical, Print, Return, Set, Stmt, This, Unary, Var, Variable, While,
+        Assert, Assign, Binary, BindingPattern, Block, Call, Class, Conditional, Defer,
+        Destructure, Expr, Expression, ForIn, Function, Get, Grouping, If, Index, Interpolation,
+        List, Literal, Logical, Map, Match, MatchArm, OptionalChain, Parameter, Pattern, Print,
+        Range, Return, Set, SetIndex, Slice, Spread, Stmt, This, Trait, Unary, Update, Var,
+        Variable, While, Yield,
     },
     error::{self, parse_error},
+    range::LoxRange,
     token::{DataType, Token, TokenType},
 };
+use substring::Substring;
 
 pub struct Parser<'a> {
     tokens: &'a [Token],
+    // The source the tokens were scanned from, an assert keeps the text of its condition.
+    source: &'a str,
     current: usize,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &[Token]) -> Parser {
-        Parser { tokens, current: 0 }
+    pub fn new(tokens: &'a [Token], source: &'a str) -> Parser<'a> {
+        Parser {
+            tokens,
+            source,
+            current: 0,
+        }
     }
     /// Parses the tokens and returns the AST.
     pub fn parse(&mut self) -> Result<Vec<Rc<dyn Stmt>>, Error> {
@@ -40,10 +51,19 @@ impl<'a> Parser<'a> {
     fn declaration(&mut self) -> Result<Rc<dyn Stmt>, Error> {
         if self.matches(&[TokenType::Class]) {
             return self.class_declaration();
+        } else if self.matches(&[TokenType::Trait]) {
+            return self.trait_declaration();
         } else if self.matches(&[TokenType::Fun]) {
+            if self.matches(&[TokenType::Star]) {
+                return self.function("generator");
+            }
             return self.function("function");
         } else if self.matches(&[TokenType::Var]) {
-            return self.var_declaration();
+            return self.var_declaration(false);
+        } else if self.matches(&[TokenType::Const]) {
+            return self.var_declaration(true);
+        } else if self.matches(&[TokenType::Defer]) {
+            return self.defer_statement();
         }
         match self.statement() {
             Ok(stmt) => Ok(stmt),
@@ -53,16 +73,106 @@ impl<'a> Parser<'a> {
             }
         }
     }
-    /// TODO describe
+    /// Parses the body of a class after the 'class' keyword.
+    /// Every member is a method, unless it is prefixed with the 'static' keyword.
+    /// A static member followed by a '(' is a static method which is called on the class itself: Math.square(3).
+    /// Any other static member is a class-level variable, declared like a var: static count = 0;
+    /// A name directly followed by a '{' is a getter: area { ... }, which runs when the property is read.
+    /// A method prefixed with 'set' is a setter: set radius(v) { ... }, which runs when the property is assigned.
+    /// Traits are included after the class name with the 'with' keyword: class Foo with Bar, Baz { ... }
     fn class_declaration(&mut self) -> Result<Rc<dyn Stmt>, Error> {
         let name = self.consume(TokenType::Identifier, "Expect class name")?;
+        let mut traits: Vec<Rc<dyn Expr>> = Vec::new();
+        if self.matches(&[TokenType::With]) {
+            loop {
+                let trait_name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(Rc::new(Variable::new(trait_name)));
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
         self.consume(TokenType::LeftBrace, "Expect '{' before class body")?;
         let mut methods = Vec::new();
-        while !self.check(TokenType::RightBrace) {
-            methods.push(self.function("method")?);
+        let mut getters = Vec::new();
+        let mut setters = Vec::new();
+        let mut static_methods = Vec::new();
+        let mut static_fields = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if self.matches(&[TokenType::Static]) {
+                if self.check_next(TokenType::LeftParen) {
+                    static_methods.push(self.function("static method")?);
+                } else {
+                    static_fields.push(self.static_field()?);
+                }
+            } else if self.peek().lexeme == "set"
+                && (self.check_next(TokenType::Identifier)
+                    || self.check_next(TokenType::PrivateIdentifier))
+            {
+                self.advance();
+                setters.push(self.function("setter")?);
+            } else if self.check_next(TokenType::LeftBrace) {
+                getters.push(self.getter()?);
+            } else {
+                methods.push(self.function("method")?);
+            }
         }
         self.consume(TokenType::RightBrace, "Expect '}' after class body")?;
-        Ok(Rc::new(Class::new(name, methods)))
+        Ok(Rc::new(Class::new(
+            name,
+            traits,
+            methods,
+            getters,
+            setters,
+            static_methods,
+            static_fields,
+        )))
+    }
+    /// A class-level variable: static count = 0; It can't be destructured, each one needs its own name.
+    /// A private name is parsed as well, so the resolver can report that a static member can't be private.
+    fn static_field(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        if !self.check(TokenType::Identifier) && !self.check(TokenType::PrivateIdentifier) {
+            return Err(self.parse_error(self.peek(), "Expect variable name."));
+        }
+        let name = self.advance().dup();
+        let initializer = if self.matches(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Rc::new(Var::new(
+            BindingPattern::Name(name),
+            initializer,
+            false,
+        )))
+    }
+    /// Parses the body of a trait after the 'trait' keyword. A trait only contains methods,
+    /// which are copied into every class that includes the trait.
+    fn trait_declaration(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+        Ok(Rc::new(Trait::new(name, methods)))
+    }
+    /// A getter is a method without a parameter list, the body directly follows its name.
+    fn getter(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let name = self.member_name("Expect getter name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+        let body = self.block()?;
+        Ok(Rc::new(Function::new(
+            name,
+            Rc::new(Vec::new()),
+            body,
+            false,
+        )))
     }
 
     /// Checks what type of statement we are dealing with and calls the corresponding function that statement.
@@ -71,14 +181,22 @@ impl<'a> Parser<'a> {
     /// we're dealing with is checked in this function
     /// and not in any of the other functions called below.
     fn statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
-        if self.matches(&[TokenType::For]) {
+        if self.matches(&[TokenType::Assert]) {
+            self.assert_statement()
+        } else if self.check(TokenType::Defer) {
+            Err(self.parse_error(self.peek(), "Can only defer in a block."))
+        } else if self.matches(&[TokenType::For]) {
             self.for_statement()
         } else if self.matches(&[TokenType::If]) {
             self.if_statement()
+        } else if self.matches(&[TokenType::Match]) {
+            self.match_statement()
         } else if self.matches(&[TokenType::Print]) {
             self.print_statement()
         } else if self.matches(&[TokenType::Return]) {
             self.return_statement()
+        } else if self.matches(&[TokenType::Yield]) {
+            self.yield_statement()
         } else if self.matches(&[TokenType::While]) {
             self.while_statement()
         } else if self.matches(&[TokenType::LeftBrace]) {
@@ -115,10 +233,14 @@ impl<'a> Parser<'a> {
     /// the same logic.
     fn for_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
-        let initializer = if self.matches(&[TokenType::Semicolon]) {
+        let declared = self.matches(&[TokenType::Var]);
+        if self.check(TokenType::Identifier) && self.check_next(TokenType::In) {
+            return self.for_in_statement();
+        }
+        let initializer = if declared {
+            Some(self.var_declaration(false)?)
+        } else if self.matches(&[TokenType::Semicolon]) {
             None
-        } else if self.matches(&[TokenType::Var]) {
-            Some(self.var_declaration()?)
         } else {
             Some(self.expression_statement()?)
         };
@@ -160,6 +282,16 @@ impl<'a> Parser<'a> {
 
         Ok(body)
     }
+    /// Parses the rest of a for-in loop: for (x in iterable) body, the 'var' before the name is optional.
+    /// The loop variable is always a new variable, every iteration gets its own.
+    fn for_in_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let keyword = self.consume(TokenType::In, "Expect 'in' after variable name.")?;
+        let iterable = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clause.")?;
+        let body = self.statement()?;
+        Ok(Rc::new(ForIn::new(name, keyword, iterable, body)))
+    }
     /// Checks if the current token is an open parenthesis by using the consume function.
     /// The parser advances and the expression is grabbed.
     /// Next it checks if the next token is a closing parenthesis. As an if statements condition needs to
@@ -193,6 +325,132 @@ impl<'a> Parser<'a> {
     /// Grabs the expression, the parser advances and via the consume function it's checked
     /// if the next token is a semicolon to finish the statement.
     /// A Print object is created using the expression and returned.
+    /// Parses match (value) { arms }, every arm is: patterns [if guard] => statement.
+    fn match_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let value = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match arms.")?;
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            arms.push(self.match_arm()?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+        Ok(Rc::new(Match::new(value, arms)))
+    }
+    /// Alternatives are separated by commas: 1, 2 => ...
+    /// They can't bind names, the body wouldn't know which of the names are bound.
+    fn match_arm(&mut self) -> Result<MatchArm, Error> {
+        let mut patterns = vec![self.pattern()?];
+        while self.matches(&[TokenType::Comma]) {
+            patterns.push(self.pattern()?);
+        }
+        if patterns.len() > 1 {
+            if let Some(name) = patterns.iter().find_map(|p| p.binding()) {
+                return Err(
+                    self.parse_error(name, "Can't bind a name in a pattern with alternatives.")
+                );
+            }
+        }
+        let guard = if self.matches(&[TokenType::If]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::EqualGreater, "Expect '=>' after match pattern.")?;
+        let body = self.statement()?;
+        Ok(MatchArm::new(patterns, guard, body))
+    }
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        if self.matches(&[TokenType::Identifier]) {
+            let name = self.previous().dup();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard(name));
+            }
+            if self.matches(&[TokenType::LeftParen]) {
+                // Point(_) and Point() match the same, neither binds the instance.
+                let binding = if self.check(TokenType::RightParen) {
+                    None
+                } else {
+                    let binding =
+                        self.consume(TokenType::Identifier, "Expect name in class pattern.")?;
+                    Some(binding).filter(|b| b.lexeme != "_")
+                };
+                self.consume(TokenType::RightParen, "Expect ')' after class pattern.")?;
+                let class = Rc::new(Variable::new(name.dup()));
+                return Ok(Pattern::Class {
+                    name,
+                    class,
+                    binding,
+                });
+            }
+            return Ok(Pattern::Binding(name));
+        }
+        let token = self.peek().dup();
+        let value = self.pattern_literal()?;
+        if self.matches(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.previous().token_type == TokenType::DotDotEqual;
+            let end = self.pattern_literal()?;
+            return match (whole_literal(&value), whole_literal(&end)) {
+                (Some(start), Some(end)) => Ok(Pattern::Range {
+                    token,
+                    range: LoxRange::new(start, end, inclusive),
+                }),
+                _ => Err(self.parse_error(&token, "Range pattern bounds must be whole numbers.")),
+            };
+        }
+        Ok(Pattern::Literal { token, value })
+    }
+    /// A literal in a pattern, numbers can be negative: -1.
+    fn pattern_literal(&mut self) -> Result<DataType, Error> {
+        if self.matches(&[TokenType::False]) {
+            return Ok(DataType::Bool(false));
+        }
+        if self.matches(&[TokenType::True]) {
+            return Ok(DataType::Bool(true));
+        }
+        if self.matches(&[TokenType::Nil]) {
+            return Ok(DataType::Nil);
+        }
+        if self.matches(&[TokenType::String]) {
+            return Ok(self.previous().literal.clone().unwrap());
+        }
+        let negative = self.matches(&[TokenType::Minus]);
+        if self.matches(&[TokenType::Number]) {
+            let value = self.previous().literal.clone().unwrap();
+            if !negative {
+                return Ok(value);
+            }
+            return Ok(match value {
+                DataType::Integer(n) => DataType::Integer(-n),
+                DataType::Number(n) => DataType::Number(-n),
+                DataType::BigInt(n) => DataType::BigInt(n.neg()),
+                DataType::Decimal(n) => DataType::Decimal(n.neg()),
+                value => value,
+            });
+        }
+        Err(self.parse_error(self.peek(), "Expect pattern."))
+    }
+    /// assert condition; or assert condition, message; The source of the condition is kept for the error.
+    fn assert_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let keyword = self.previous().dup();
+        let start = self.current;
+        let condition = self.expression()?;
+        let text = self.source_text(start);
+        let mut message = None;
+        if self.matches(&[TokenType::Comma]) {
+            message = Some(self.expression()?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after assertion.")?;
+        Ok(Rc::new(Assert::new(keyword, condition, message, text)))
+    }
+    /// defer statement; The deferred statement belongs to the block the defer is in,
+    /// so like a declaration it can't be the body of an if or a loop.
+    fn defer_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let keyword = self.previous().dup();
+        let stmt = self.statement()?;
+        Ok(Rc::new(Defer::new(keyword, stmt)))
+    }
     fn print_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
         let value = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
@@ -214,24 +472,86 @@ impl<'a> Parser<'a> {
         self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
         Ok(Rc::new(Return::new(keyword, value)))
     }
+    /// yield value; or yield; which yields nil. The resolver checks that it's in the body of a generator.
+    fn yield_statement(&mut self) -> Result<Rc<dyn Stmt>, Error> {
+        let keyword = self.previous().dup();
+        let mut value = None;
+        if !self.check(TokenType::Semicolon) {
+            value = Some(self.expression()?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after yield value.")?;
+        Ok(Rc::new(Yield::new(keyword, value)))
+    }
     /// Consumes the current token in the parser which should be the name of the variable.
     /// Next it checks if the next token is an = token. If it is, it grabs the expression of the next token
     /// and applies it as the variables value.
     /// Before creating and returning the Var object, consume is called, this is to check if the next token is a semicolon.
     /// If it is not, it will throw an error, as all statements should end with a semicolon (;).
-    fn var_declaration(&mut self) -> Result<Rc<dyn Stmt>, Error> {
-        // Variable names are lexed as Identifier tokens.
-        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+    /// A const declaration is parsed like a var declaration, but it needs a value since it can't be assigned later.
+    fn var_declaration(&mut self, constant: bool) -> Result<Rc<dyn Stmt>, Error> {
+        let pattern = self.binding_pattern()?;
         let initializer = if self.matches(&[TokenType::Equal]) {
             Some(self.expression()?)
         } else {
             None
         };
+        if initializer.is_none() && !matches!(pattern, BindingPattern::Name(_)) {
+            let msg = "Expect '=' after a destructuring pattern.";
+            return Err(self.parse_error(self.peek(), msg));
+        }
+        if initializer.is_none() && constant {
+            return Err(self.parse_error(self.peek(), "Expect '=' after constant name."));
+        }
         self.consume(
             TokenType::Semicolon,
             "Expect ';' after variable declaration.",
         )?;
-        Ok(Rc::new(Var::new(name.dup(), initializer)))
+        Ok(Rc::new(Var::new(pattern, initializer, constant)))
+    }
+    /// A variable name, or a list of names between brackets or braces to destructure a value into: [a, b] or {x, y}.
+    fn binding_pattern(&mut self) -> Result<BindingPattern, Error> {
+        let (closing, message) = if self.matches(&[TokenType::LeftBracket]) {
+            (
+                TokenType::RightBracket,
+                "Expect ']' after destructuring pattern.",
+            )
+        } else if self.matches(&[TokenType::LeftBrace]) {
+            (
+                TokenType::RightBrace,
+                "Expect '}' after destructuring pattern.",
+            )
+        } else {
+            // Variable names are lexed as Identifier tokens.
+            let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+            return Ok(BindingPattern::Name(name));
+        };
+        let opening = self.previous().dup();
+        let mut names = Vec::<Token>::new();
+        if !self.check(closing) {
+            loop {
+                let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+                if names.iter().any(|n| n.lexeme == name.lexeme) {
+                    let msg = format!("'{}' is bound more than once.", name.lexeme);
+                    return Err(self.parse_error(&name, &msg));
+                }
+                names.push(name);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(closing, message)?;
+        if closing == TokenType::RightBracket {
+            Ok(BindingPattern::List {
+                bracket: opening,
+                names,
+            })
+        } else {
+            Ok(BindingPattern::Map {
+                brace: opening,
+                names,
+            })
+        }
     }
     /// Calls the consume function to check for a left paren. If it is not, it will throw an error.
     /// A While statements condition should be between parentheses.
@@ -267,24 +587,51 @@ impl<'a> Parser<'a> {
     /// curly brace. As the next block is the body of the function.
     /// The block function is called to grab the functions body and the established parameters, body and function name
     /// are put into a Function object.
+    /// A generator function is declared with fun* instead of fun.
     fn function(&mut self, kind: &str) -> Result<Rc<dyn Stmt>, Error> {
         let kind_error = format!("Expect {} name.", kind);
-        let name = self.consume(TokenType::Identifier, kind_error.as_str())?;
+        // Only members of a class can be private.
+        let name = if kind == "function" || kind == "generator" {
+            self.consume(TokenType::Identifier, kind_error.as_str())?
+        } else {
+            self.member_name(kind_error.as_str())?
+        };
         let paren_error = format!("Expect '(' after {kind} name.");
         self.consume(TokenType::LeftParen, paren_error.as_str())?;
 
-        let mut parameters = Vec::<Token>::new();
+        let mut parameters = Vec::<Parameter>::new();
 
         if !self.check(TokenType::RightParen) {
             loop {
                 if parameters.len() >= 255 {
                     error::parse_error(self.peek(), "Can't have more than 255 parameters.");
                 }
-                parameters.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                let rest = self.matches(&[TokenType::DotDotDot]);
+                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                // Once a parameter has a default value, the ones after it need one as well, except a rest parameter.
+                let default = if rest {
+                    if self.check(TokenType::Equal) {
+                        let msg = "A rest parameter can't have a default value.";
+                        return Err(self.parse_error(self.peek(), msg));
+                    }
+                    None
+                } else if self.matches(&[TokenType::Equal]) {
+                    Some(self.expression()?)
+                } else if parameters.iter().any(|p| p.default.is_some()) {
+                    let msg = "Expect default value for parameter after a parameter with a default value.";
+                    return Err(self.parse_error(&name, msg));
+                } else {
+                    None
+                };
+                parameters.push(Parameter::new(name, default, rest));
 
                 if !self.matches(&[TokenType::Comma]) {
                     break;
                 }
+                if rest {
+                    let msg = "A rest parameter must be the last parameter.";
+                    return Err(self.parse_error(self.previous(), msg));
+                }
             }
         }
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
@@ -292,7 +639,13 @@ impl<'a> Parser<'a> {
         self.consume(TokenType::LeftBrace, block_error.as_str())?;
 
         let body = self.block()?;
-        Ok(Rc::new(Function::new(name, Rc::new(parameters), body)))
+        let generator = kind == "generator";
+        Ok(Rc::new(Function::new(
+            name,
+            Rc::new(parameters),
+            body,
+            generator,
+        )))
     }
 
     fn block(&mut self) -> Result<Rc<Vec<Rc<dyn Stmt>>>, Error> {
@@ -310,9 +663,10 @@ impl<'a> Parser<'a> {
     /// can be grabbed.
     /// A new Assignment object is returned with the variable's name and the value we got via recursion.
     /// If the expression is of type 'Get', it will assign the value to the field instead.
-    /// If the expression is not of type 'Variable' or 'Get' (get = accessing field), it will return the expression.
+    /// If the expression is of type 'Index', it will assign the value to that element of the list: list[0] = 1.
+    /// If the expression is not of type 'Variable', 'Get' or 'Index', it will return the expression.
     fn assignment(&mut self) -> Result<Rc<dyn Expr>, Error> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
         if self.matches(&[TokenType::Equal]) {
             // We get the equals sign as token so we can use it for an error message.
             let equals = self.previous().dup();
@@ -322,13 +676,78 @@ impl<'a> Parser<'a> {
                 None => {}
             }
             match expr.as_any().downcast_ref::<Get>() {
-                Some(get) => {
+                Some(get) if !get.optional => {
                     return Ok(Rc::new(Set::new(get.object.clone(), get.name.dup(), value)))
                 }
-                None => {}
+                // obj?.field = value is not allowed, there would be nothing to assign to when obj is nil.
+                _ => {}
+            }
+            if let Some(index) = expr.as_any().downcast_ref::<Index>() {
+                return Ok(Rc::new(SetIndex::new(
+                    index.object.clone(),
+                    index.bracket.dup(),
+                    index.index.clone(),
+                    value,
+                )));
+            }
+            if let Some(list) = expr.as_any().downcast_ref::<List>() {
+                let mut targets = Vec::<Rc<dyn Expr>>::new();
+                for element in list.elements.iter() {
+                    targets.push(self.update_target(element.clone(), &equals)?);
+                }
+                return Ok(Rc::new(Destructure::new(targets, equals, value)));
             }
             return Err(self.parse_error(&equals, "Invalid assignment target."));
         }
+        if self.matches(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let operator = self.previous().dup();
+            let value = self.assignment()?;
+            let target = self.update_target(expr, &operator)?;
+            return Ok(Rc::new(Update::new(target, operator, Some(value), true)));
+        }
+        Ok(expr)
+    }
+    /// Checks that the target of a compound assignment, ++ or -- can be assigned to.
+    /// The same targets as a normal assignment are allowed: a variable, a field or a list element.
+    fn update_target(&self, target: Rc<dyn Expr>, operator: &Token) -> Result<Rc<dyn Expr>, Error> {
+        let assignable = target.as_any().downcast_ref::<Variable>().is_some()
+            || target.as_any().downcast_ref::<Index>().is_some()
+            || matches!(target.as_any().downcast_ref::<Get>(), Some(get) if !get.optional);
+        if !assignable {
+            return Err(self.parse_error(operator, "Invalid assignment target."));
+        }
+        Ok(target)
+    }
+    /// Parses a conditional expression: condition ? then : else.
+    /// The else branch is parsed as a conditional again, this makes it right-associative:
+    /// a ? b : c ? d : e is a ? b : (c ? d : e).
+    fn conditional(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let expr = self.nil_coalescing()?;
+        if self.matches(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Rc::new(Conditional::new(expr, then_branch, else_branch)));
+        }
+        Ok(expr)
+    }
+    /// Parses the ?? operator, the right side is only evaluated if the left side is nil: name ?? "anonymous".
+    /// It short-circuits like OR does, so it's a Logical object as well.
+    fn nil_coalescing(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut expr = self.or()?;
+        while self.matches(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous().dup();
+            let right = self.or()?;
+            expr = Rc::new(Logical::new(expr, operator, right))
+        }
         Ok(expr)
     }
     /// Grabs the expression by going down the precedence tree.
@@ -367,8 +786,37 @@ impl<'a> Parser<'a> {
     /// We have the expression, the operator
     /// and because the parser has advanced we grab the expression on the right side.
     fn equality(&mut self) -> Result<Rc<dyn Expr>, Error> {
-        let mut expr = self.comparison();
+        let mut expr = self.bit_or();
         while self.matches(&[TokenType::EqualEqual, TokenType::BangEqual]) {
+            let operator = self.previous().dup();
+            let right = self.bit_or();
+            expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
+        }
+        expr
+    }
+    /// The bitwise operators bind tighter than equality, unlike C: flags & 1 == 0 is (flags & 1) == 0.
+    /// From loosest to tightest they are |, ^ and &.
+    fn bit_or(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut expr = self.bit_xor();
+        while self.matches(&[TokenType::Pipe]) {
+            let operator = self.previous().dup();
+            let right = self.bit_xor();
+            expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
+        }
+        expr
+    }
+    fn bit_xor(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut expr = self.bit_and();
+        while self.matches(&[TokenType::Caret]) {
+            let operator = self.previous().dup();
+            let right = self.bit_and();
+            expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
+        }
+        expr
+    }
+    fn bit_and(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut expr = self.comparison();
+        while self.matches(&[TokenType::Ampersand]) {
             let operator = self.previous().dup();
             let right = self.comparison();
             expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
@@ -379,15 +827,38 @@ impl<'a> Parser<'a> {
     /// If the next Token in the parser is a comparison a binary object is created.
     /// We have the expression that will be on the left side, the operator
     /// and because the parser has advanced we grab the expression on the right side.
+    /// The 'in' operator tests membership and is parsed as a comparison as well: x in 1..10.
     fn comparison(&mut self) -> Result<Rc<dyn Expr>, Error> {
-        let mut expr = self.term();
+        let mut expr = self.range();
         let comparison_vec = vec![
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::In,
         ];
         while self.matches(&comparison_vec) {
+            let operator = self.previous().dup();
+            let right = self.range();
+            expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
+        }
+        expr
+    }
+    /// Parses a range: 1..10 or 1..=10. The bounds bind tighter, 0..n - 1 is 0..(n - 1).
+    /// A range doesn't chain, 1..2..3 is an error.
+    fn range(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let expr = self.shift()?;
+        if self.matches(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let operator = self.previous().dup();
+            let end = self.shift()?;
+            return Ok(Rc::new(Range::new(expr, operator, end)));
+        }
+        Ok(expr)
+    }
+    /// Parses the << and >> operators, they bind looser than + and -: 1 << 2 + 1 is 1 << 3.
+    fn shift(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut expr = self.term();
+        while self.matches(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous().dup();
             let right = self.term();
             expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
@@ -413,30 +884,63 @@ impl<'a> Parser<'a> {
     /// If the token is not a unary operator, it will return the factor.
     /// If the token is not a unary operator and not a factor, it will return an error.
     /// If the token is a factor, it will return the factor by making a binary expression.
-    /// The binary expression contains out of the unary expression * or / and then another unary expression.
-    /// Valid examples: 1 * 3, 20 / 3. "hello" - world, would also parse,
+    /// The binary expression contains out of the unary expression *, /, % or ~/ and then another unary expression.
+    /// Valid examples: 1 * 3, 20 / 3, 7 % 2, 7 ~/ 2. "hello" - world, would also parse,
     /// however during interpreting this will be caught as an error.
     fn factor(&mut self) -> Result<Rc<dyn Expr>, Error> {
         let mut expr = self.unary();
-        while self.matches(&[TokenType::Star, TokenType::Slash]) {
+        while self.matches(&[
+            TokenType::Star,
+            TokenType::Slash,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous().dup();
             let right = self.unary();
             expr = Ok(Rc::new(Binary::new(expr?, operator.clone(), right?)));
         }
         expr
     }
-    /// Returns the unary operator if it exists. (!, or -)
-    /// If it doesn't exist, it'll return an expression from the call method.
+    /// Returns the unary operator if it exists. (!, - or ~)
+    /// If it doesn't exist, it'll return an expression from the power method.
     /// If it does exist, it'll return an Unary expression with an operator and the expression after it
     /// example: !false, -a
     /// The reason the right hand side will not give back the same expression is because the parser has advanced already.
     fn unary(&mut self) -> Result<Rc<dyn Expr>, Error> {
-        if self.matches(&[TokenType::Bang, TokenType::Minus]) {
+        if self.matches(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().dup();
             let right = self.unary();
             return Ok(Rc::new(Unary::new(operator, right?)));
         }
-        self.call()
+        if self.matches(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().dup();
+            let target = self.unary()?;
+            let target = self.update_target(target, &operator)?;
+            return Ok(Rc::new(Update::new(target, operator, None, true)));
+        }
+        self.power()
+    }
+    /// Parses the ** operator, which binds tighter than a unary operator on its left: -2 ** 2 is -(2 ** 2).
+    /// The right hand side is parsed as a unary expression, this makes the operator right-associative
+    /// (2 ** 3 ** 2 is 2 ** (3 ** 2)) and allows a negative exponent: 2 ** -1.
+    fn power(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let expr = self.postfix()?;
+        if self.matches(&[TokenType::StarStar]) {
+            let operator = self.previous().dup();
+            let right = self.unary()?;
+            return Ok(Rc::new(Binary::new(expr, operator, right)));
+        }
+        Ok(expr)
+    }
+    /// Parses a postfix ++ or --, which gives the value from before the update: i++.
+    fn postfix(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let expr = self.call()?;
+        if self.matches(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().dup();
+            let target = self.update_target(expr, &operator)?;
+            return Ok(Rc::new(Update::new(target, operator, None, false)));
+        }
+        Ok(expr)
     }
     /// This function returns a Call object and is used to get the arguments of a function call.
     /// The function starts by checking if the current token in the parser isn't a right parenthesis.
@@ -451,22 +955,36 @@ impl<'a> Parser<'a> {
     /// This new argument is given from the expression function. Every time this function is called the parser advances to the next token.
     /// If the next token isn't a comma it will break the 'do while loop' as this means this was the last argument of the function.
     /// This is because function arguments are separated by a comma. Example: doSomething(one, two three)
+    /// A named argument is a parameter name followed by a colon: f(1, b: 2).
     fn finish_call(&mut self, callee: Rc<dyn Expr>) -> Result<Rc<dyn Expr>, Error> {
         let mut arguments = Vec::<Rc<dyn Expr>>::new();
+        let mut named = Vec::<(Token, Rc<dyn Expr>)>::new();
         if !self.check(TokenType::RightParen) {
             loop {
                 // The limit of a function's argument count is now 254. It only reports an error, it doesn't return one.
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     self.parse_error(self.peek(), "Can't have more than 255 arguments.");
                 }
-                arguments.push(self.expression()?);
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                    let name = self.advance().dup();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if !named.is_empty() {
+                    let msg = "Positional arguments can't come after named arguments.";
+                    return Err(self.parse_error(self.peek(), msg));
+                } else if self.matches(&[TokenType::DotDotDot]) {
+                    let operator = self.previous().dup();
+                    arguments.push(Rc::new(Spread::new(operator, self.expression()?)));
+                } else {
+                    arguments.push(self.expression()?);
+                }
                 if !self.matches(&[TokenType::Comma]) {
                     break;
                 }
             }
         }
         let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
-        Ok(Rc::new(Call::new(callee, paren, arguments)))
+        Ok(Rc::new(Call::new(callee, paren, arguments, named)))
     }
     /// grabs an expression containing a datatype from primary.
     /// The parser advances and it loops until the current token in the parser isn't a left paranthesis or period.
@@ -478,25 +996,80 @@ impl<'a> Parser<'a> {
     /// If the current token is a period a property of an object is being accessed.
     /// The parser consumes by checking if the next token is an identifier and uses the result
     /// to create a get object which handles property access.
+    /// A ?. instead of a dot makes the property access optional: obj?.field is nil when obj is nil.
+    /// A chain with a ?. in it is wrapped in an OptionalChain, so a nil skips the rest of it: obj?.a.b is nil as well.
+    /// If the current token is a left bracket an element of a list is being accessed: list[0].
+    /// A colon between the brackets takes a slice instead: list[1:3], list[1:] or list[:3].
     /// When the loop is done the new expression is returned.
     fn call(&mut self) -> Result<Rc<dyn Expr>, Error> {
         let mut expr = self.primary();
+        let mut optional = false;
         loop {
             if self.matches(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr?);
             } else if self.matches(&[TokenType::Dot]) {
-                let name =
-                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
-                expr = Ok(Rc::new(Get::new(expr?, name.clone())));
+                let name = self.property_name("Expect property name after '.'.")?;
+                expr = Ok(Rc::new(Get::new(expr?, name.clone(), false)));
+            } else if self.matches(&[TokenType::QuestionDot]) {
+                let name = self.property_name("Expect property name after '?.'.")?;
+                expr = Ok(Rc::new(Get::new(expr?, name.clone(), true)));
+                optional = true;
+            } else if self.matches(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().dup();
+                let start = if self.check(TokenType::Colon) {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                if self.matches(&[TokenType::Colon]) {
+                    let end = if self.check(TokenType::RightBracket) {
+                        None
+                    } else {
+                        Some(self.expression()?)
+                    };
+                    self.consume(TokenType::RightBracket, "Expect ']' after slice.")?;
+                    expr = Ok(Rc::new(Slice::new(expr?, bracket, start, end)));
+                } else {
+                    self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                    expr = Ok(Rc::new(Index::new(expr?, bracket, start.unwrap())));
+                }
             } else {
                 break;
             }
         }
+        if optional {
+            return Ok(Rc::new(OptionalChain::new(expr?)));
+        }
         expr
     }
+    /// Parses the rest of an interpolated string, the first interpolation token is already consumed.
+    /// Every interpolation token is followed by an expression, the string token after the last expression ends it.
+    /// "a ${b} c" becomes the parts "a ", b and " c".
+    fn interpolation(&mut self) -> Result<Rc<dyn Expr>, Error> {
+        let mut parts = Vec::<Rc<dyn Expr>>::new();
+        loop {
+            parts.push(Rc::new(Literal::new(self.previous().literal.clone())));
+            // The string goes on after the }, so a token that starts with } right away means the ${ } is empty.
+            let goes_on = self.check(TokenType::String) || self.check(TokenType::Interpolation);
+            if goes_on && self.peek().lexeme.starts_with('}') {
+                return Err(self.parse_error(self.previous(), "Expect expression."));
+            }
+            parts.push(self.expression()?);
+            if !self.matches(&[TokenType::Interpolation]) {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::String,
+            "Expect '}' after interpolated expression.",
+        )?;
+        parts.push(Rc::new(Literal::new(self.previous().literal.clone())));
+        Ok(Rc::new(Interpolation::new(parts)))
+    }
     /// Primary method returns a data value wrapped in an Unary object.
     /// This is the base of the expression tree you could say.
     /// It can be a literal (10, "hello world", false), a variable (input, age), a parenthesized expression (2 + 2)
+    /// or a list of comma separated expressions between brackets ([1, 2, 3]).
     fn primary(&mut self) -> Result<Rc<dyn Expr>, Error> {
         if self.matches(&[TokenType::False]) {
             return Ok(Rc::new(Literal::new(Some(DataType::Bool(false)))));
@@ -515,6 +1088,10 @@ impl<'a> Parser<'a> {
             ))));
         }
 
+        if self.matches(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+
         if self.matches(&[TokenType::This]) {
             return Ok(Rc::new(This::new(self.previous().dup())));
         }
@@ -529,6 +1106,38 @@ impl<'a> Parser<'a> {
             return Ok(Rc::new(Grouping::new(expr?)));
         }
 
+        if self.matches(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::<Rc<dyn Expr>>::new();
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Rc::new(List::new(elements)));
+        }
+
+        // A brace can only start a map here, a brace at the start of a statement is a block.
+        if self.matches(&[TokenType::LeftBrace]) {
+            let brace = self.previous().dup();
+            let mut entries = Vec::<(Rc<dyn Expr>, Rc<dyn Expr>)>::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    entries.push((key, self.expression()?));
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Rc::new(Map::new(brace, entries)));
+        }
+
         Err(self.parse_error(self.peek(), "Expect expression."))
     }
     /// Loops over the given token types in the parameter.
@@ -543,6 +1152,31 @@ impl<'a> Parser<'a> {
         }
         false
     }
+    /// Consumes the name of a class member, which is either a regular or a private (#name) identifier.
+    fn member_name(&mut self, message: &str) -> Result<Token, Error> {
+        if self.matches(&[TokenType::PrivateIdentifier]) {
+            Ok(self.previous().dup())
+        } else {
+            self.consume(TokenType::Identifier, message)
+        }
+    }
+    /// The name after a '.' can be the keyword yield too, for Fiber.yield.
+    fn property_name(&mut self, message: &str) -> Result<Token, Error> {
+        if self.matches(&[TokenType::Yield]) {
+            let mut name = self.previous().dup();
+            name.token_type = TokenType::Identifier;
+            Ok(name)
+        } else {
+            self.member_name(message)
+        }
+    }
+    /// The source from the token at start up to the end of the token before the current one, as it was written.
+    fn source_text(&self, start: usize) -> String {
+        let first = self.tokens[start].offset;
+        let last = self.previous();
+        let end = last.offset + last.lexeme.chars().count();
+        self.source.substring(first, end).to_string()
+    }
     /// Gets the current Token in the parser and advances to the next one.
     fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, Error> {
         if self.check(token_type) {
@@ -564,8 +1198,13 @@ impl<'a> Parser<'a> {
             }
             match self.peek().token_type {
                 TokenType::Class
+                | TokenType::Trait
+                | TokenType::Static
                 | TokenType::Fun
                 | TokenType::Var
+                | TokenType::Const
+                | TokenType::Defer
+                | TokenType::Assert
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
@@ -587,6 +1226,13 @@ impl<'a> Parser<'a> {
         }
         return self.peek().token_type == token_type;
     }
+    /// Compares the token after the current one with the TokenType given in the parameter.
+    fn check_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
     /// Advances to the next token in the parser.
     /// Returns the previous token.
     fn advance(&mut self) -> &Token {
@@ -611,3 +1257,11 @@ impl<'a> Parser<'a> {
         self.tokens.get(self.current - 1).unwrap()
     }
 }
+
+/// The value of a whole number literal in a range pattern.
+fn whole_literal(value: &DataType) -> Option<i64> {
+    match value {
+        DataType::Integer(n) => Some(*n),
+        _ => None,
+    }
+}
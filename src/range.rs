@@ -0,0 +1,98 @@
+use std::fmt::{self, Display, Formatter};
+
+/// A range of whole numbers, written as start..end or start..=end in Lox.
+/// The values are only made when they are needed, so a range can be far larger than a list.
+/// A negative step counts down from start towards end: (10..0).stepBy(-2) is 10, 8, 6, 4, 2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoxRange {
+    start: i64,
+    end: i64,
+    step: i64,
+    inclusive: bool,
+}
+
+impl LoxRange {
+    pub fn new(start: i64, end: i64, inclusive: bool) -> LoxRange {
+        LoxRange {
+            start,
+            end,
+            step: 1,
+            inclusive,
+        }
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    pub fn step(&self) -> i64 {
+        self.step
+    }
+
+    /// Gives back None for a step of zero, such a range would never reach its end.
+    pub fn step_by(&self, step: i64) -> Option<LoxRange> {
+        if step == 0 {
+            return None;
+        }
+        Some(LoxRange { step, ..*self })
+    }
+
+    /// The amount of values in the range.
+    pub fn count(&self) -> u64 {
+        let (start, end, step) = (self.start as i128, self.end as i128, self.step as i128);
+        let inclusive = if self.inclusive { 1 } else { 0 };
+        // The distance from the start to the first number past the end.
+        let span = if step > 0 {
+            end - start + inclusive
+        } else {
+            start - end + inclusive
+        };
+        if span <= 0 {
+            return 0;
+        }
+        ((span + step.abs() - 1) / step.abs()) as u64
+    }
+
+    pub fn contains(&self, n: i64) -> bool {
+        let distance = n as i128 - self.start as i128;
+        let step = self.step as i128;
+        distance % step == 0 && (0..self.count() as i128).contains(&(distance / step))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> {
+        let (start, step) = (self.start as i128, self.step as i128);
+        (0..self.count() as i128).map(move |i| (start + i * step) as i64)
+    }
+
+    /// The start and the exclusive end of the range, when it can be used as the bounds of a slice.
+    pub fn slice_bounds(&self) -> Option<(i64, i64)> {
+        if self.step != 1 {
+            return None;
+        }
+        if self.inclusive {
+            Some((self.start, self.end.checked_add(1)?))
+        } else {
+            Some((self.start, self.end))
+        }
+    }
+}
+
+/// A range prints as the expression that makes it.
+impl Display for LoxRange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let operator = if self.inclusive { "..=" } else { ".." };
+        if self.step == 1 {
+            write!(f, "{}{}{}", self.start, operator, self.end)
+        } else {
+            write!(
+                f,
+                "({}{}{}).stepBy({})",
+                self.start, operator, self.end, self.step
+            )
+        }
+    }
+}
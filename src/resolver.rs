@@ -2,13 +2,15 @@ use std::{borrow::BorrowMut, cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
     ast::{
-        Assign, Binary, Block, Call, Class, Expr, ExprVisitor, Expression, Function, Get, Grouping,
-        If, Literal, Logical, Print, Return, Set, Stmt, StmtVisitor, Super, This, Unary, Var,
-        Variable, VisitorTypes, While,
+        Assert, Assign, Binary, Block, Call, Class, Conditional, Defer, Destructure, Expr,
+        ExprVisitor, Expression, ForIn, Function, Get, Grouping, If, Index, Interpolation, List,
+        Literal, Logical, Map, Match, OptionalChain, Pattern, Print, Range, Return, Set, SetIndex,
+        Slice, Spread, Stmt, StmtVisitor, Super, This, Trait, Unary, Update, Var, Variable,
+        VisitorTypes, While, Yield,
     },
-    error,
+    class, error,
     interpreter::Interpreter,
-    token::Token,
+    token::{DataType, Token},
 };
 
 pub struct Resolver<'a> {
@@ -16,18 +18,27 @@ pub struct Resolver<'a> {
     scopes: RefCell<Vec<RefCell<HashMap<String, bool>>>>,
     current_function: RefCell<FunctionType>,
     current_class: RefCell<ClassType>,
+    // The method names of every declared trait, used to report conflicts between included traits.
+    traits: RefCell<HashMap<String, Vec<String>>>,
+    // The declarations of the constants in every scope, assigning to one is reported together with its declaration.
+    constants: RefCell<Vec<HashMap<String, Token>>>,
 }
 #[derive(PartialEq)]
 enum FunctionType {
     None,
     Function,
+    Generator,
     Method,
     Initializer,
+    // A deferred statement runs after its block is done, so it can't return or yield for the function it is in.
+    Deferred,
 }
 #[derive(PartialEq)]
 enum ClassType {
     None,
     Class,
+    // Inside a static method, there is no instance for 'this' to refer to.
+    Static,
 }
 
 impl<'a> Resolver<'a> {
@@ -37,6 +48,8 @@ impl<'a> Resolver<'a> {
             scopes: RefCell::new(Vec::new()),
             current_function: RefCell::new(FunctionType::None),
             current_class: RefCell::new(ClassType::None),
+            traits: RefCell::new(HashMap::new()),
+            constants: RefCell::new(Vec::new()),
         }
     }
 
@@ -66,21 +79,127 @@ impl<'a> Resolver<'a> {
     fn resolve_function(&mut self, stmt: &Function, func_type: FunctionType) {
         let enclosing_function = self.current_function.replace(func_type);
         self.begin_scope();
+        // A default value can use the parameters before it, but not its own parameter or the ones after it.
         for param in stmt.params.iter() {
-            self.declare(param.dup());
-            self.define(param.dup());
+            if let Some(default) = &param.default {
+                self.resolve_expr(default);
+            }
+            self.declare(param.name.dup());
+            self.define(param.name.dup());
         }
         self.resolve(&stmt.body);
         self.end_scope();
         self.current_function.replace(enclosing_function);
     }
 
+    /// Private members (#name) can only be accessed through 'this' inside the class that declares them.
+    fn check_private_access(&self, object: &Rc<dyn Expr>, name: &Token) {
+        if !class::is_private(&name.lexeme) {
+            return;
+        }
+        if *self.current_class.borrow() == ClassType::None {
+            let msg = format!(
+                "Can't access private member '{}' outside of a class.",
+                name.lexeme
+            );
+            error::resolve_error(name, &msg);
+        } else if object.as_any().downcast_ref::<This>().is_none() {
+            let msg = format!(
+                "Private member '{}' can only be accessed through 'this'.",
+                name.lexeme
+            );
+            error::resolve_error(name, &msg);
+        }
+    }
+
+    /// Private members are reached through 'this', which a static member doesn't have.
+    fn check_private_static(&self, name: &Token) {
+        if class::is_private(&name.lexeme) {
+            let msg = format!("Static member '{}' can't be private.", name.lexeme);
+            error::resolve_error(name, &msg);
+        }
+    }
+
+    /// Reports every method that is defined by more than one included trait and not overridden by the class itself.
+    fn check_trait_conflicts(&self, stmt: &Class) {
+        let own_methods: Vec<String> = stmt
+            .methods
+            .iter()
+            .filter_map(|m| m.as_any().downcast_ref::<Function>())
+            .map(|f| f.name.dup().lexeme)
+            .collect();
+        let mut included: HashMap<String, String> = HashMap::new();
+        for trait_expr in stmt.traits.iter() {
+            let name = match trait_expr.as_any().downcast_ref::<Variable>() {
+                Some(v) => v.name.dup(),
+                None => continue,
+            };
+            if let Some(methods) = self.traits.borrow().get(&name.lexeme) {
+                for method in methods.iter() {
+                    if own_methods.contains(method) {
+                        continue;
+                    }
+                    if let Some(other) = included.get(method) {
+                        let msg = format!(
+                            "Method '{}' is defined by both trait '{}' and trait '{}'.",
+                            method, other, name.lexeme
+                        );
+                        error::resolve_error(&name, &msg);
+                    } else {
+                        included.insert(method.clone(), name.dup().lexeme);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports arms that can never run because the arms before them, without a guard, already match all of their patterns.
+    fn check_unreachable_arms(&self, stmt: &Match) {
+        let mut earlier: Vec<&Pattern> = Vec::new();
+        for arm in stmt.arms.iter() {
+            let covered = arm
+                .patterns
+                .iter()
+                .all(|pattern| earlier.iter().any(|e| pattern_covers(e, pattern)));
+            if covered {
+                let token = arm.patterns[0].token();
+                error::resolve_error(
+                    token,
+                    "Unreachable match arm, an earlier arm already matches.",
+                );
+            }
+            if arm.guard.is_none() {
+                earlier.extend(arm.patterns.iter());
+            }
+        }
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.borrow_mut().push(RefCell::new(HashMap::new()));
+        self.constants.borrow_mut().push(HashMap::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.borrow_mut().pop();
+        self.constants.borrow_mut().pop();
+    }
+
+    /// Reports an assignment to a local constant. Global constants are checked when the assignment runs.
+    fn check_assignable(&self, name: &Token) {
+        let scopes = self.scopes.borrow();
+        let constants = self.constants.borrow();
+        for (scope, constants) in scopes.iter().zip(constants.iter()).rev() {
+            if scope.borrow().contains_key(&name.lexeme) {
+                if let Some(declaration) = constants.get(&name.lexeme) {
+                    let msg = format!(
+                        "Can't assign to constant '{}' declared at line {}.",
+                        name.lexeme, declaration.line
+                    );
+                    error::resolve_error(name, &msg);
+                }
+                return;
+            }
+        }
     }
 
     fn declare(&mut self, name: Token) {
@@ -105,6 +224,7 @@ impl<'a> ExprVisitor for Resolver<'a> {
         let value = expr.value.clone();
         let expr: Rc<dyn Expr> = Rc::new(Assign::new(expr.name.dup(), value.clone()));
         self.resolve_expr(&value);
+        self.check_assignable(&name);
         self.resolve_local(Rc::clone(&expr), &name);
         VisitorTypes::Void(())
     }
@@ -120,10 +240,32 @@ impl<'a> ExprVisitor for Resolver<'a> {
         for arg in expr.arguments.iter() {
             self.resolve_expr(arg);
         }
+        for (_, arg) in expr.named.iter() {
+            self.resolve_expr(arg);
+        }
+        VisitorTypes::Void(())
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> VisitorTypes {
+        self.resolve_expr(&expr.condition);
+        self.resolve_expr(&expr.then_branch);
+        self.resolve_expr(&expr.else_branch);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_destructure_expr(&mut self, expr: &Destructure) -> VisitorTypes {
+        self.resolve_expr(&expr.value);
+        for target in expr.targets.iter() {
+            if let Some(variable) = target.as_any().downcast_ref::<Variable>() {
+                self.check_assignable(&variable.name);
+            }
+            self.resolve_expr(target);
+        }
         VisitorTypes::Void(())
     }
 
     fn visit_get_expr(&mut self, expr: &Get) -> VisitorTypes {
+        self.check_private_access(&expr.object, &expr.name);
         self.resolve_expr(&expr.object);
         VisitorTypes::Void(())
     }
@@ -133,6 +275,26 @@ impl<'a> ExprVisitor for Resolver<'a> {
         VisitorTypes::Void(())
     }
 
+    fn visit_index_expr(&mut self, expr: &Index) -> VisitorTypes {
+        self.resolve_expr(&expr.object);
+        self.resolve_expr(&expr.index);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Interpolation) -> VisitorTypes {
+        for part in expr.parts.iter() {
+            self.resolve_expr(part);
+        }
+        VisitorTypes::Void(())
+    }
+
+    fn visit_list_expr(&mut self, expr: &List) -> VisitorTypes {
+        for element in expr.elements.iter() {
+            self.resolve_expr(element);
+        }
+        VisitorTypes::Void(())
+    }
+
     fn visit_literal_expr(&mut self, _: &Literal) -> VisitorTypes {
         VisitorTypes::Void(())
     }
@@ -143,9 +305,52 @@ impl<'a> ExprVisitor for Resolver<'a> {
         VisitorTypes::Void(())
     }
 
+    fn visit_map_expr(&mut self, expr: &Map) -> VisitorTypes {
+        for (key, value) in expr.entries.iter() {
+            self.resolve_expr(key);
+            self.resolve_expr(value);
+        }
+        VisitorTypes::Void(())
+    }
+
+    fn visit_optional_chain_expr(&mut self, expr: &OptionalChain) -> VisitorTypes {
+        self.resolve_expr(&expr.expression);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_range_expr(&mut self, expr: &Range) -> VisitorTypes {
+        self.resolve_expr(&expr.start);
+        self.resolve_expr(&expr.end);
+        VisitorTypes::Void(())
+    }
+
     fn visit_set_expr(&mut self, expr: &Set) -> VisitorTypes {
+        self.check_private_access(&expr.object, &expr.name);
+        self.resolve_expr(&expr.value);
+        self.resolve_expr(&expr.object);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> VisitorTypes {
         self.resolve_expr(&expr.value);
         self.resolve_expr(&expr.object);
+        self.resolve_expr(&expr.index);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Slice) -> VisitorTypes {
+        self.resolve_expr(&expr.object);
+        if let Some(start) = &expr.start {
+            self.resolve_expr(start);
+        }
+        if let Some(end) = &expr.end {
+            self.resolve_expr(end);
+        }
+        VisitorTypes::Void(())
+    }
+
+    fn visit_spread_expr(&mut self, expr: &Spread) -> VisitorTypes {
+        self.resolve_expr(&expr.expr);
         VisitorTypes::Void(())
     }
 
@@ -154,8 +359,14 @@ impl<'a> ExprVisitor for Resolver<'a> {
     }
 
     fn visit_this_expr(&mut self, expr: &This) -> VisitorTypes {
-        if *self.current_class.borrow() == ClassType::None {
-            error::resolve_error(&expr.keyword, "Cannot use 'this' outside of a class.");
+        match *self.current_class.borrow() {
+            ClassType::None => {
+                error::resolve_error(&expr.keyword, "Cannot use 'this' outside of a class.")
+            }
+            ClassType::Static => {
+                error::resolve_error(&expr.keyword, "Cannot use 'this' in a static method.")
+            }
+            ClassType::Class => (),
         }
         let dyn_expr: Rc<dyn Expr> = Rc::new(This::new(expr.keyword.dup()));
         self.resolve_local(dyn_expr, &expr.keyword.dup());
@@ -167,6 +378,19 @@ impl<'a> ExprVisitor for Resolver<'a> {
         VisitorTypes::Void(())
     }
 
+    /// A variable target is read and written through the same Variable expression,
+    /// resolving the target once resolves both.
+    fn visit_update_expr(&mut self, expr: &Update) -> VisitorTypes {
+        if let Some(value) = &expr.value {
+            self.resolve_expr(value);
+        }
+        if let Some(variable) = expr.target.as_any().downcast_ref::<Variable>() {
+            self.check_assignable(&variable.name);
+        }
+        self.resolve_expr(&expr.target);
+        VisitorTypes::Void(())
+    }
+
     fn visit_variable_expr(&mut self, expr: &Variable) -> VisitorTypes {
         let token = expr.name.dup();
         if !self.scopes.borrow().is_empty()
@@ -189,6 +413,14 @@ impl<'a> ExprVisitor for Resolver<'a> {
 }
 
 impl<'a> StmtVisitor for Resolver<'a> {
+    fn visit_assert_stmt(&mut self, stmt: &Assert) -> VisitorTypes {
+        self.resolve_expr(&stmt.condition);
+        if let Some(message) = &stmt.message {
+            self.resolve_expr(message);
+        }
+        VisitorTypes::Void(())
+    }
+
     fn visit_block_stmt(&mut self, stmt: &Block) -> VisitorTypes {
         self.begin_scope();
         self.resolve(&stmt.statements);
@@ -197,9 +429,29 @@ impl<'a> StmtVisitor for Resolver<'a> {
     }
 
     fn visit_class_stmt(&mut self, stmt: &Class) -> VisitorTypes {
-        let enclosing_class = self.current_class.replace(ClassType::Class);
+        let enclosing_class = self.current_class.replace(ClassType::Static);
         self.declare(stmt.name.dup());
         self.define(stmt.name.dup());
+        for trait_expr in stmt.traits.iter() {
+            self.resolve_expr(trait_expr);
+        }
+        self.check_trait_conflicts(stmt);
+        // Static members live outside of the scope that binds 'this'.
+        for field in stmt.static_fields.iter() {
+            if let Some(var) = field.as_any().downcast_ref::<Var>() {
+                self.check_private_static(var.pattern.token());
+                if let Some(initializer) = &var.initializer {
+                    self.resolve_expr(initializer);
+                }
+            }
+        }
+        for method in stmt.static_methods.iter() {
+            if let Some(m) = method.as_any().downcast_ref::<Function>() {
+                self.check_private_static(&m.name);
+                self.resolve_function(m, FunctionType::Method);
+            }
+        }
+        self.current_class.replace(ClassType::Class);
         self.begin_scope();
         self.scopes
             .borrow()
@@ -208,6 +460,19 @@ impl<'a> StmtVisitor for Resolver<'a> {
             .unwrap()
             .borrow_mut()
             .insert("this".to_string(), true);
+        for getter in stmt.getters.iter() {
+            if let Some(g) = getter.as_any().downcast_ref::<Function>() {
+                self.resolve_function(g, FunctionType::Method);
+            }
+        }
+        for setter in stmt.setters.iter() {
+            if let Some(s) = setter.as_any().downcast_ref::<Function>() {
+                if s.params.len() != 1 {
+                    error::resolve_error(&s.name, "A setter must have exactly one parameter.");
+                }
+                self.resolve_function(s, FunctionType::Method);
+            }
+        }
         for method in stmt.methods.iter() {
             let mut declaration = FunctionType::Method;
             match method.as_any().downcast_ref::<Function>() {
@@ -225,16 +490,39 @@ impl<'a> StmtVisitor for Resolver<'a> {
         VisitorTypes::Void(())
     }
 
+    fn visit_defer_stmt(&mut self, stmt: &Defer) -> VisitorTypes {
+        let enclosing_function = self.current_function.replace(FunctionType::Deferred);
+        self.resolve_stmt(&stmt.stmt);
+        self.current_function.replace(enclosing_function);
+        VisitorTypes::Void(())
+    }
+
     fn visit_expression_stmt(&mut self, stmt: &Expression) -> VisitorTypes {
         self.resolve_expr(&stmt.expression);
         VisitorTypes::Void(())
     }
 
+    /// The loop variable lives in its own scope around the body, the interpreter makes a new one every iteration.
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> VisitorTypes {
+        self.resolve_expr(&stmt.iterable);
+        self.begin_scope();
+        self.declare(stmt.name.dup());
+        self.define(stmt.name.dup());
+        self.resolve_stmt(&stmt.body);
+        self.end_scope();
+        VisitorTypes::Void(())
+    }
+
     fn visit_function_stmt(&mut self, stmt: &Function) -> VisitorTypes {
         let name = stmt.name.dup();
         self.declare(name.dup());
         self.define(name);
-        self.resolve_function(stmt, FunctionType::Function);
+        let func_type = if stmt.generator {
+            FunctionType::Generator
+        } else {
+            FunctionType::Function
+        };
+        self.resolve_function(stmt, func_type);
         VisitorTypes::Void(())
     }
 
@@ -247,6 +535,30 @@ impl<'a> StmtVisitor for Resolver<'a> {
         VisitorTypes::Void(())
     }
 
+    /// Every arm has its own scope for the name its pattern binds.
+    fn visit_match_stmt(&mut self, stmt: &Match) -> VisitorTypes {
+        self.resolve_expr(&stmt.value);
+        self.check_unreachable_arms(stmt);
+        for arm in stmt.arms.iter() {
+            for pattern in arm.patterns.iter() {
+                if let Pattern::Class { class, .. } = pattern {
+                    self.resolve_expr(class);
+                }
+            }
+            self.begin_scope();
+            for name in arm.patterns.iter().filter_map(|p| p.binding()) {
+                self.declare(name.dup());
+                self.define(name.dup());
+            }
+            if let Some(guard) = &arm.guard {
+                self.resolve_expr(guard);
+            }
+            self.resolve_stmt(&arm.body);
+            self.end_scope();
+        }
+        VisitorTypes::Void(())
+    }
+
     fn visit_print_stmt(&mut self, stmt: &Print) -> VisitorTypes {
         self.resolve_expr(&stmt.expression);
         VisitorTypes::Void(())
@@ -256,22 +568,73 @@ impl<'a> StmtVisitor for Resolver<'a> {
         if *self.current_function.borrow() == FunctionType::None {
             error::resolve_error(&stmt.keyword, "Can't return from top-level code.");
         }
+        if *self.current_function.borrow() == FunctionType::Deferred {
+            error::resolve_error(&stmt.keyword, "Can't return from a deferred statement.");
+        }
 
         if let Some(value) = &stmt.value {
             if *self.current_function.borrow_mut() == FunctionType::Initializer {
                 error::resolve_error(&stmt.keyword, "Can't return a value from an initializer.");
             }
+            // A generator hands out its values with yield, a return only ends it.
+            if *self.current_function.borrow() == FunctionType::Generator {
+                error::resolve_error(&stmt.keyword, "Can't return a value from a generator.");
+            }
             self.resolve_expr(value);
         }
         VisitorTypes::Void(())
     }
 
-    fn visit_var_stmt(&mut self, stmt: &Var) -> VisitorTypes {
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> VisitorTypes {
         self.declare(stmt.name.dup());
+        self.define(stmt.name.dup());
+        let methods: Vec<String> = stmt
+            .methods
+            .iter()
+            .filter_map(|m| m.as_any().downcast_ref::<Function>())
+            .map(|f| f.name.dup().lexeme)
+            .collect();
+        self.traits
+            .borrow_mut()
+            .insert(stmt.name.dup().lexeme, methods);
+
+        let enclosing_class = self.current_class.replace(ClassType::Class);
+        self.begin_scope();
+        if let Some(scope) = self.scopes.borrow().last() {
+            scope.borrow_mut().insert("this".to_string(), true);
+        }
+        for method in stmt.methods.iter() {
+            if let Some(m) = method.as_any().downcast_ref::<Function>() {
+                let declaration = if m.name.lexeme == "init" {
+                    FunctionType::Initializer
+                } else {
+                    FunctionType::Method
+                };
+                self.resolve_function(m, declaration);
+            }
+        }
+        self.end_scope();
+        self.current_class.replace(enclosing_class);
+        VisitorTypes::Void(())
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Var) -> VisitorTypes {
+        for name in stmt.pattern.names() {
+            self.declare(name.dup());
+        }
         if let Some(initializer) = &stmt.initializer {
             self.resolve_expr(initializer);
         }
-        self.define(stmt.name.dup());
+        for name in stmt.pattern.names() {
+            self.define(name.dup());
+        }
+        if stmt.constant {
+            if let Some(constants) = self.constants.borrow_mut().last_mut() {
+                for name in stmt.pattern.names() {
+                    constants.insert(name.lexeme.clone(), name.dup());
+                }
+            }
+        }
         VisitorTypes::Void(())
     }
 
@@ -280,4 +643,54 @@ impl<'a> StmtVisitor for Resolver<'a> {
         self.resolve_stmt(&stmt.body.clone());
         VisitorTypes::Void(())
     }
+
+    fn visit_yield_stmt(&mut self, stmt: &Yield) -> VisitorTypes {
+        if *self.current_function.borrow() == FunctionType::Deferred {
+            error::resolve_error(&stmt.keyword, "Can't yield from a deferred statement.");
+        } else if *self.current_function.borrow() != FunctionType::Generator {
+            error::resolve_error(
+                &stmt.keyword,
+                "Can't yield outside of a generator function.",
+            );
+        }
+        if let Some(value) = &stmt.value {
+            self.resolve_expr(value);
+        }
+        VisitorTypes::Void(())
+    }
+}
+
+/// Whether every value the later pattern matches is matched by the earlier one as well.
+fn pattern_covers(earlier: &Pattern, later: &Pattern) -> bool {
+    match (earlier, later) {
+        (Pattern::Binding(_) | Pattern::Wildcard(_), _) => true,
+        (Pattern::Literal { value: a, .. }, Pattern::Literal { value: b, .. }) => {
+            same_literal(a, b)
+        }
+        (
+            Pattern::Range { range, .. },
+            Pattern::Literal {
+                value: DataType::Integer(n),
+                ..
+            },
+        ) => range.contains(*n),
+        (Pattern::Range { range: a, .. }, Pattern::Range { range: b, .. }) => a == b,
+        (Pattern::Class { name: a, .. }, Pattern::Class { name: b, .. }) => a.lexeme == b.lexeme,
+        _ => false,
+    }
+}
+
+fn same_literal(a: &DataType, b: &DataType) -> bool {
+    match (a, b) {
+        (DataType::String(a), DataType::String(b)) => a == b,
+        (DataType::Bool(a), DataType::Bool(b)) => a == b,
+        (DataType::Nil, DataType::Nil) => true,
+        (DataType::Integer(a), DataType::Integer(b)) => a == b,
+        (DataType::Number(a), DataType::Number(b)) => a == b,
+        (DataType::Integer(a), DataType::Number(b))
+        | (DataType::Number(b), DataType::Integer(a)) => *a as f64 == *b,
+        (DataType::BigInt(a), DataType::BigInt(b)) => a == b,
+        (DataType::Decimal(a), DataType::Decimal(b)) => a == b,
+        _ => false,
+    }
 }
@@ -1,4 +1,6 @@
 use crate::{
+    bignum::{BigInt, Decimal},
+    config::{self, IntOverflow},
     error,
     token::{DataType, Token, TokenType},
 };
@@ -7,21 +9,27 @@ use substring::Substring;
 
 pub struct Scanner {
     source: String,
+    // The source is read by character, its length in bytes would be too long for text that isn't ASCII.
+    length: usize,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: u32,
     pos: u32,
+    // The depth of unclosed braces inside each ${ } of a string that is being interpolated.
+    interpolations: Vec<usize>,
 }
 impl Scanner {
     pub fn new(source: String) -> Self {
         Scanner {
+            length: source.chars().count(),
             source,
             tokens: Vec::<Token>::new(),
             start: 0,
             current: 0,
             line: 1,
             pos: 0,
+            interpolations: Vec::new(),
         }
     }
 
@@ -38,6 +46,7 @@ impl Scanner {
             None,
             self.line,
             self.pos,
+            self.current,
         ));
         &self.tokens
     }
@@ -50,21 +59,112 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            // A } that isn't matched by a { in an interpolation ends it, the string continues after it.
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string();
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace);
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '.' => {
+                if self.matches('.') {
+                    if self.matches('.') {
+                        self.add_token(TokenType::DotDotDot);
+                    } else {
+                        self.pick_and_add_token(TokenType::DotDotEqual, TokenType::DotDot, '=');
+                    }
+                } else {
+                    self.add_token(TokenType::Dot);
+                }
+            }
+            '-' => {
+                if self.matches('-') {
+                    self.add_token(TokenType::MinusMinus);
+                } else {
+                    self.pick_and_add_token(TokenType::MinusEqual, TokenType::Minus, '=');
+                }
+            }
+            '+' => {
+                if self.matches('+') {
+                    self.add_token(TokenType::PlusPlus);
+                } else {
+                    self.pick_and_add_token(TokenType::PlusEqual, TokenType::Plus, '=');
+                }
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
+            ':' => self.add_token(TokenType::Colon),
+            '%' => self.add_token(TokenType::Percent),
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
             // These lexemes can be either a one or two character long token.
             // The pick and add token fn, will handle this.
             '!' => self.pick_and_add_token(TokenType::BangEqual, TokenType::Bang, '='),
-            '=' => self.pick_and_add_token(TokenType::EqualEqual, TokenType::Equal, '='),
-            '<' => self.pick_and_add_token(TokenType::LessEqual, TokenType::Less, '='),
-            '>' => self.pick_and_add_token(TokenType::GreaterEqual, TokenType::Greater, '='),
+            '=' => {
+                if self.matches('>') {
+                    self.add_token(TokenType::EqualGreater);
+                } else {
+                    self.pick_and_add_token(TokenType::EqualEqual, TokenType::Equal, '=');
+                }
+            }
+            '<' => {
+                if self.matches('<') {
+                    self.add_token(TokenType::LessLess);
+                } else {
+                    self.pick_and_add_token(TokenType::LessEqual, TokenType::Less, '=');
+                }
+            }
+            '>' => {
+                if self.matches('>') {
+                    self.add_token(TokenType::GreaterGreater);
+                } else {
+                    self.pick_and_add_token(TokenType::GreaterEqual, TokenType::Greater, '=');
+                }
+            }
+            '*' => {
+                if self.matches('*') {
+                    self.add_token(TokenType::StarStar);
+                } else {
+                    self.pick_and_add_token(TokenType::StarEqual, TokenType::Star, '=');
+                }
+            }
+            '?' => {
+                if self.matches('.') {
+                    self.add_token(TokenType::QuestionDot);
+                } else {
+                    self.pick_and_add_token(TokenType::QuestionQuestion, TokenType::Question, '?');
+                }
+            }
+            // Floor division is written ~/ since // always starts a comment.
+            '~' => self.pick_and_add_token(TokenType::TildeSlash, TokenType::Tilde, '/'),
             '"' => self.string(),
+            // A string prefixed with r is raw, it has no escape sequences.
+            'r' if self.peek() == '"' => {
+                self.advance();
+                self.raw_string();
+            }
+            // Private class members are prefixed with a #, the # is part of the name.
+            '#' => {
+                if self.is_alpha(self.peek()) {
+                    self.private_identifier();
+                } else {
+                    error::error(self.line, self.pos, "Expect member name after '#'.")
+                }
+            }
             '/' => {
                 // This is for single line comments
                 if self.matches('/') {
@@ -85,7 +185,7 @@ impl Scanner {
                         }
                     }
                 } else {
-                    self.add_token(TokenType::Slash);
+                    self.pick_and_add_token(TokenType::SlashEqual, TokenType::Slash, '=');
                 }
             } /* */
             // Ignore whitespace
@@ -95,7 +195,7 @@ impl Scanner {
             // Default case
             _ => {
                 if self.is_digit(c) {
-                    self.number();
+                    self.number(c);
                 } else if self.is_alpha(c) {
                     self.identifier();
                 } else {
@@ -119,13 +219,119 @@ impl Scanner {
         }
     }
 
+    /// Scans the name of a private class member like #balance.
+    fn private_identifier(&mut self) {
+        while self.is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+        self.add_token(TokenType::PrivateIdentifier);
+    }
+
     /// Scans a string literal.
+    /// A backslash starts an escape sequence: \n, \t, \r, \\, \", \$, \0 or a unicode escape like \u{1F600}.
+    /// A string with ${expression} in it is split up: every part before a ${ becomes an interpolation token,
+    /// followed by the tokens of the expression. The part after the last } is a normal string token.
     fn string(&mut self) {
+        let mut value = String::new();
+        let mut is_valid = true;
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+                self.interpolations.push(0);
+                if is_valid {
+                    let value = Some(DataType::String(value));
+                    self.add_token_advanced(TokenType::Interpolation, value);
+                }
+                return;
+            }
+            match self.advance() {
+                '\n' => {
+                    self.new_line();
+                    value.push('\n');
+                }
+                '\\' => match self.escape() {
+                    Some(c) => value.push(c),
+                    None => is_valid = false,
+                },
+                c => value.push(c),
+            }
+        }
+        if self.is_at_end() {
+            error::error(self.line, self.pos, "Unterminated string.");
+            return;
+        }
+        self.advance();
+        // The error is already reported, the rest of the string is still consumed so scanning can go on after it.
+        if is_valid {
+            self.add_token_advanced(TokenType::String, Some(DataType::String(value)));
+        }
+    }
+
+    /// Scans the escape sequence after a backslash.
+    /// Reports an error and gives back None when the escape sequence is not valid.
+    fn escape(&mut self) -> Option<char> {
+        if self.is_at_end() {
+            return None;
+        }
+        let escaped = match self.advance() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            '0' => '\0',
+            'u' => return self.unicode_escape(),
+            c => {
+                if c == '\n' {
+                    self.new_line();
+                }
+                let msg = format!("Unknown escape sequence '\\{c}'.");
+                error::error(self.line, self.pos, &msg);
+                return None;
+            }
+        };
+        Some(escaped)
+    }
+
+    /// Scans the {1F600} part of a unicode escape, it holds 1 to 6 hexadecimal digits.
+    fn unicode_escape(&mut self) -> Option<char> {
+        if self.peek() != '{' {
+            error::error(self.line, self.pos, "Expect '{' after '\\u'.");
+            return None;
+        }
+        self.advance();
+        let mut digits = String::new();
+        while self.peek().is_ascii_hexdigit() {
+            digits.push(self.advance());
+        }
+        if self.peek() != '}' {
+            error::error(self.line, self.pos + 1, "Expect '}' after unicode escape.");
+            return None;
+        }
+        self.advance();
+        if digits.is_empty() || digits.len() > 6 {
+            let msg = "A unicode escape needs 1 to 6 hexadecimal digits.";
+            error::error(self.line, self.pos, msg);
+            return None;
+        }
+        let c = u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32);
+        if c.is_none() {
+            let msg = format!("Invalid unicode code point '{digits}'.");
+            error::error(self.line, self.pos, &msg);
+        }
+        c
+    }
+
+    /// Scans a raw string literal like r"C:\path", backslashes are kept as they are.
+    fn raw_string(&mut self) {
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.advance() == '\n' {
                 self.new_line();
             }
-            self.advance();
         }
         if self.is_at_end() {
             error::error(self.line, self.pos, "Unterminated string.");
@@ -134,15 +340,28 @@ impl Scanner {
         self.advance();
         let value = self
             .source
-            .substring(self.start + 1, self.current - 1)
+            .substring(self.start + 2, self.current - 1)
             .to_string();
         self.add_token_advanced(TokenType::String, Some(DataType::String(value)));
     }
 
     /// Scans a number literal.
-    fn number(&mut self) {
-        while self.is_digit(self.peek()) {
+    /// Integers can be written in hexadecimal (0xFF), binary (0b1010) or octal (0o17),
+    /// an exponent (1e9, 2.5E-3) makes a float and underscores can separate digits (1_000_000).
+    fn number(&mut self, first: char) {
+        let radix = match (first, self.peek()) {
+            ('0', 'x') | ('0', 'X') => Some(16),
+            ('0', 'b') | ('0', 'B') => Some(2),
+            ('0', 'o') | ('0', 'O') => Some(8),
+            _ => None,
+        };
+        if let Some(radix) = radix {
             self.advance();
+            self.radix_number(radix);
+            return;
+        }
+        if !self.digits(10) {
+            return;
         }
         // Look for fractional part.
         // Looking past decimal point requires a second character of lookeahead
@@ -150,19 +369,122 @@ impl Scanner {
         if self.peek() == '.' && self.is_digit(self.peek_next()) {
             // Consume the "."
             self.advance();
-            while self.is_digit(self.peek()) {
+            if !self.digits(10) {
+                return;
+            }
+        }
+        let has_exponent = self.peek() == 'e' || self.peek() == 'E';
+        if has_exponent {
+            self.advance();
+            if self.peek() == '+' || self.peek() == '-' {
                 self.advance();
             }
+            if !self.is_digit(self.peek()) {
+                error::error(self.line, self.pos, "Expect digits in the exponent.");
+                return;
+            }
+            if !self.digits(10) {
+                return;
+            }
+        }
+        // Grab the substring containing just the number, without the separators.
+        let value = self
+            .source
+            .substring(self.start, self.current)
+            .replace('_', "");
+        // A suffix makes the literal an exact number: 123n is a big integer, 1.10d a decimal.
+        if !has_exponent
+            && (self.peek() == 'n' || self.peek() == 'd')
+            && !self.is_alpha_numeric(self.peek_next())
+        {
+            let value = match self.advance() {
+                'n' if value.contains('.') => {
+                    let msg = "A big integer literal can't have a fractional part.";
+                    error::error(self.line, self.pos, msg);
+                    return;
+                }
+                'n' => DataType::BigInt(BigInt::parse(&value).unwrap()),
+                _ => DataType::Decimal(Decimal::parse(&value).unwrap()),
+            };
+            self.add_token_advanced(TokenType::Number, Some(value));
+            return;
         }
-        // Grab the substring containing just the number.
-        let value = self.source.substring(self.start, self.current).to_string();
         // Wrap and convert it to the right datatype.
-        let value = Some(DataType::Number(value.parse::<f64>().unwrap()));
-        self.add_token_advanced(TokenType::Number, value);
+        // Numbers without a decimal point or exponent are integers, unless they don't fit in one.
+        let value = if value.contains('.') || has_exponent {
+            DataType::Number(value.parse::<f64>().unwrap())
+        } else if let Ok(n) = value.parse::<i64>() {
+            DataType::Integer(n)
+        } else if config::get_int_overflow() == IntOverflow::Promote {
+            DataType::Number(value.parse::<f64>().unwrap())
+        } else {
+            error::error(self.line, self.pos, "Integer literal is too large.");
+            return;
+        };
+        self.add_token_advanced(TokenType::Number, Some(value));
+    }
+
+    /// Scans the digits of a hexadecimal, binary or octal integer, the prefix is already consumed.
+    fn radix_number(&mut self, radix: u32) {
+        let name = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            _ => "binary",
+        };
+        if !self.peek().is_digit(radix) {
+            let msg = format!(
+                "Expect {name} digits after '{}'.",
+                self.source.substring(self.start, self.current)
+            );
+            error::error(self.line, self.pos, &msg);
+            return;
+        }
+        if !self.digits(radix) {
+            return;
+        }
+        // A letter or digit right after the literal is a digit that doesn't belong to the radix: 0b102.
+        if self.is_alpha_numeric(self.peek()) {
+            let c = self.advance();
+            error::error(
+                self.line,
+                self.pos,
+                &format!("Invalid digit '{c}' in {name} literal."),
+            );
+            return;
+        }
+        let digits = self
+            .source
+            .substring(self.start + 2, self.current)
+            .replace('_', "");
+        let value = if let Ok(n) = i64::from_str_radix(&digits, radix) {
+            DataType::Integer(n)
+        } else if config::get_int_overflow() == IntOverflow::Promote {
+            let n = digits.chars().fold(0.0, |n, c| {
+                n * radix as f64 + c.to_digit(radix).unwrap() as f64
+            });
+            DataType::Number(n)
+        } else {
+            error::error(self.line, self.pos, "Integer literal is too large.");
+            return;
+        };
+        self.add_token_advanced(TokenType::Number, Some(value));
+    }
+
+    /// Consumes the digits of a number in the given radix, an underscore may separate two digits: 1_000.
+    /// Reports an error and returns false when an underscore isn't followed by a digit.
+    fn digits(&mut self, radix: u32) -> bool {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            if self.advance() == '_' && !self.peek().is_digit(radix) {
+                let msg = "Expect a digit after '_' in a number.";
+                error::error(self.line, self.pos, msg);
+                return false;
+            }
+        }
+        true
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.source.len()
+        self.current >= self.length
     }
     /// Advance the current index by one. And returns the char at the current index.
     fn advance(&mut self) -> char {
@@ -193,8 +515,9 @@ impl Scanner {
 
     fn add_token_advanced(&mut self, token_type: TokenType, literal: Option<DataType>) {
         let text = self.source.substring(self.start, self.current).to_string();
-        self.tokens
-            .push(Token::new(token_type, text, literal, self.line, self.pos));
+        self.tokens.push(Token::new(
+            token_type, text, literal, self.line, self.pos, self.start,
+        ));
     }
 
     /// Checks if the next char is the same as the given char. This is used to check for lexemes of two characters.
@@ -219,7 +542,7 @@ impl Scanner {
 
     /// Peeks the 2nd upcoming character without advancing the current index.
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
+        if self.current + 1 >= self.length {
             return '\0';
         }
         self.source.chars().nth(self.current + 1).unwrap()
@@ -246,21 +569,130 @@ lazy_static! {
     pub static ref KEYWORDS: HashMap<String, TokenType> = {
         let mut m = HashMap::new();
         m.insert(String::from("and"), TokenType::And);
+        m.insert(String::from("assert"), TokenType::Assert);
         m.insert(String::from("class"), TokenType::Class);
+        m.insert(String::from("const"), TokenType::Const);
+        m.insert(String::from("defer"), TokenType::Defer);
         m.insert(String::from("else"), TokenType::Else);
         m.insert(String::from("false"), TokenType::False);
         m.insert(String::from("fun"), TokenType::Fun);
         m.insert(String::from("for"), TokenType::For);
         m.insert(String::from("if"), TokenType::If);
+        m.insert(String::from("in"), TokenType::In);
+        m.insert(String::from("match"), TokenType::Match);
         m.insert(String::from("nil"), TokenType::Nil);
         m.insert(String::from("or"), TokenType::Or);
         m.insert(String::from("print"), TokenType::Print);
         m.insert(String::from("return"), TokenType::Return);
+        m.insert(String::from("static"), TokenType::Static);
         m.insert(String::from("super"), TokenType::Super);
         m.insert(String::from("this"), TokenType::This);
+        m.insert(String::from("trait"), TokenType::Trait);
         m.insert(String::from("true"), TokenType::True);
         m.insert(String::from("var"), TokenType::Var);
         m.insert(String::from("while"), TokenType::While);
+        m.insert(String::from("with"), TokenType::With);
+        m.insert(String::from("yield"), TokenType::Yield);
         m
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scans a single number literal and gives its value, None when the scanner didn't produce a number.
+    fn number(source: &str) -> Option<DataType> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        tokens
+            .iter()
+            .find(|token| token.token_type == TokenType::Number)
+            .and_then(|token| token.literal.clone())
+    }
+
+    fn integer(source: &str) -> i64 {
+        match number(source) {
+            Some(DataType::Integer(n)) => n,
+            other => panic!("{} scanned as {:?}", source, other),
+        }
+    }
+
+    fn float(source: &str) -> f64 {
+        match number(source) {
+            Some(DataType::Number(n)) => n,
+            other => panic!("{} scanned as {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn radix_literals() {
+        assert_eq!(integer("0xFF"), 255);
+        assert_eq!(integer("0Xff"), 255);
+        assert_eq!(integer("0b1010"), 10);
+        assert_eq!(integer("0o17"), 15);
+        assert_eq!(integer("0xFF_FF"), 65535);
+    }
+
+    #[test]
+    fn exponent_literals_are_floats() {
+        assert_eq!(float("1e9"), 1e9);
+        assert_eq!(float("2.5E-3"), 2.5e-3);
+        assert_eq!(float("1e+2"), 100.0);
+    }
+
+    #[test]
+    fn underscores_separate_digits() {
+        assert_eq!(integer("1_000_000"), 1_000_000);
+        assert_eq!(float("1_000.000_5"), 1000.0005);
+    }
+
+    #[test]
+    fn invalid_literals_give_no_number() {
+        for source in ["0x", "0b", "1_", "1__0", "1e", "0b102"] {
+            assert!(number(source).is_none(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn floor_division_and_comments() {
+        let types = |source: &str| -> Vec<TokenType> {
+            let mut scanner = Scanner::new(source.to_string());
+            scanner.scan_tokens().iter().map(|t| t.token_type).collect()
+        };
+        assert_eq!(
+            types("a ~/ 2 ~ 1"),
+            [
+                TokenType::Identifier,
+                TokenType::TildeSlash,
+                TokenType::Number,
+                TokenType::Tilde,
+                TokenType::Number,
+                TokenType::Eof
+            ]
+        );
+        // A // after an operand starts a comment as well, the expression goes on on the next line.
+        assert_eq!(
+            types("10 // base price\n  + 5;"),
+            [
+                TokenType::Number,
+                TokenType::Plus,
+                TokenType::Number,
+                TokenType::Semicolon,
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(
+            types("set radius(v) // stores the radius\n{"),
+            [
+                TokenType::Identifier,
+                TokenType::Identifier,
+                TokenType::LeftParen,
+                TokenType::Identifier,
+                TokenType::RightParen,
+                TokenType::LeftBrace,
+                TokenType::Eof
+            ]
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use strum_macros::Display;
 
 use crate::{
-    class::{LoxClass, LoxInstance},
+    bignum::{BigInt, Decimal},
+    class::{LoxClass, LoxInstance, LoxTrait},
+    fiber::LoxFiber,
     function::{LoxFunction, LoxNative},
+    generator::LoxGenerator,
+    range::LoxRange,
 };
 use core::fmt::{Debug, Display};
-use std::rc::Rc;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
 #[derive(Debug, Clone)]
 pub struct Token {
@@ -14,6 +18,8 @@ pub struct Token {
     pub literal: Option<DataType>,
     pub line: u32,
     pub pos: u32,
+    /// Where the lexeme starts in the source, counted in characters.
+    pub offset: usize,
 }
 impl Token {
     pub fn new(
@@ -22,6 +28,7 @@ impl Token {
         literal: Option<DataType>,
         line: u32,
         pos: u32,
+        offset: usize,
     ) -> Self {
         Token {
             token_type,
@@ -29,6 +36,7 @@ impl Token {
             literal,
             line,
             pos,
+            offset,
         }
     }
     pub fn dup(&self) -> Token {
@@ -38,79 +46,183 @@ impl Token {
             literal: self.literal.clone(),
             line: self.line,
             pos: self.pos,
+            offset: self.offset,
         }
     }
 }
 #[derive(Display, Clone, Debug, PartialEq, Copy, Hash)]
 pub enum TokenType {
     // single-character tokens.
+    Ampersand,
+    Caret,
+    Colon,
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
+    Percent,
+    Pipe,
     Plus,
     Semicolon,
     Slash,
     Star,
+    Tilde,
     // one or two character tokens.
     Bang,
     BangEqual,
+    DotDot,
+    DotDotDot,
+    DotDotEqual,
     Equal,
     EqualEqual,
+    EqualGreater,
     Greater,
     GreaterEqual,
+    GreaterGreater,
     Less,
     LessEqual,
+    LessLess,
+    MinusEqual,
+    MinusMinus,
+    PlusEqual,
+    PlusPlus,
+    Question,
+    QuestionDot,
+    QuestionQuestion,
+    SlashEqual,
+    StarEqual,
+    StarStar,
+    TildeSlash,
     // literals.
     Identifier,
+    PrivateIdentifier,
     String,
+    // The part of a string before a ${.
+    Interpolation,
     Number,
     // keywords.
     And,
+    Assert,
     Class,
+    Const,
+    Defer,
     Else,
     False,
     Fun,
     For,
     If,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
+    Static,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
+    Yield,
     Eof,
 }
 
+/// Lists are shared, every copy of a list value sees the same elements.
+pub type LoxList = Rc<RefCell<Vec<DataType>>>;
+
+/// Maps are shared like lists. Keys are strings, they are kept sorted so maps print and iterate in a stable order.
+pub type LoxMap = Rc<RefCell<BTreeMap<String, DataType>>>;
+
 #[derive(Debug, Clone)]
 pub enum DataType {
     String(String),
     Number(f64),
+    Integer(i64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Bool(bool),
     Nil,
     Function(LoxFunction),
     Native(LoxNative),
     Class(LoxClass),
+    Trait(LoxTrait),
     Instance(Rc<LoxInstance>),
+    List(LoxList),
+    Map(LoxMap),
+    Range(LoxRange),
+    Generator(LoxGenerator),
+    Fiber(LoxFiber),
 }
 
 impl Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_nested(f, &mut Vec::new())
+    }
+}
+
+impl DataType {
+    /// Seen holds the lists and maps that are being written, one that contains itself is written as [...] or {...}.
+    fn fmt_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        seen: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
         match self {
             DataType::String(s) => write!(f, "{s}"),
             DataType::Number(n) => write!(f, "{n}"),
+            DataType::Integer(n) => write!(f, "{n}"),
+            DataType::BigInt(n) => write!(f, "{n}"),
+            DataType::Decimal(n) => write!(f, "{n}"),
             DataType::Bool(b) => write!(f, "{b}"),
             DataType::Nil => write!(f, "NIL"),
             DataType::Function(fnc) => write!(f, "{fnc}"),
             DataType::Native(n) => write!(f, "{n}"),
             DataType::Class(c) => write!(f, "{c}"),
+            DataType::Trait(t) => write!(f, "{t}"),
             DataType::Instance(i) => write!(f, "{i}"),
+            DataType::List(l) => {
+                let ptr = Rc::as_ptr(l) as *const ();
+                if seen.contains(&ptr) {
+                    return write!(f, "[...]");
+                }
+                seen.push(ptr);
+                write!(f, "[")?;
+                for (i, element) in l.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmt_nested(f, seen)?;
+                }
+                seen.pop();
+                write!(f, "]")
+            }
+            DataType::Map(m) => {
+                let ptr = Rc::as_ptr(m) as *const ();
+                if seen.contains(&ptr) {
+                    return write!(f, "{{...}}");
+                }
+                seen.push(ptr);
+                write!(f, "{{")?;
+                for (i, (key, value)) in m.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: ")?;
+                    value.fmt_nested(f, seen)?;
+                }
+                seen.pop();
+                write!(f, "}}")
+            }
+            DataType::Range(r) => write!(f, "{r}"),
+            DataType::Generator(g) => write!(f, "{g}"),
+            DataType::Fiber(fiber) => write!(f, "{fiber}"),
         }
     }
 }
@@ -0,0 +1,679 @@
+use std::{env, fs, process::Command};
+
+/// Runs a program with the rlox binary, gives what it printed and its exit code.
+/// The "SRC: <path>" line printed after a program that ran without errors is left out.
+fn run(name: &str, source: &str) -> (String, i32) {
+    let path = env::temp_dir().join(format!("rlox-{}-{name}.lox", std::process::id()));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .arg(&path)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stdout = match stdout.rfind("SRC: ") {
+        Some(end) => stdout[..end].to_string(),
+        None => stdout,
+    };
+    (stdout, output.status.code().unwrap())
+}
+
+#[test]
+fn list_that_contains_itself_is_printed_once() {
+    let (out, code) = run(
+        "cyclic_list",
+        "var l = [1, 2]; l[0] = l; println(l); var shared = [1]; println([shared, shared]);",
+    );
+    assert_eq!(out, "[[...], 2]\n[[1], [1]]\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn map_that_contains_itself_is_printed_once() {
+    let (out, code) = run(
+        "cyclic_map",
+        "var m = {\"a\": 1}; m[\"self\"] = m; m[\"l\"] = [m]; print m;",
+    );
+    assert_eq!(out, "{a: 1, l: [{...}], self: {...}}");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn floor_division_and_remainder_round_towards_negative_infinity() {
+    let (out, code) = run(
+        "floor_division",
+        "println(7 ~/ 2); println(-7 ~/ 2); println(7.5 ~/ 2); println(-7n ~/ 2n);\n\
+         println(7 % 3); println(-7 % 2); println(7 % -2); println(2 ** 3 ** 2);",
+    );
+    assert_eq!(out, "3\n-4\n3\n-4\n1\n1\n-1\n512\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn double_slash_always_starts_a_comment() {
+    let (out, code) = run(
+        "double_slash",
+        "var base = 3;\n\
+         var total = 10 // base price\n\
+           + 5;\n\
+         println(total);\n\
+         class Circle {\n\
+           set radius(v) // stores the radius\n\
+           { this.r = v; }\n\
+         }\n\
+         var c = Circle(); c.radius = 2; println(c.r);\n\
+         if (total > 1) // a comment after the condition\n\
+           println(total ~/ 4);",
+    );
+    assert_eq!(out, "15\n2\n3\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn division_by_zero_is_a_runtime_error() {
+    for (i, division) in [
+        "1 / 0",
+        "1.5 / 0.0",
+        "1 ~/ 0",
+        "1.5 ~/ 0.0",
+        "1 % 0",
+        "1.5 % 0.0",
+        "1n / 0n",
+        "1n ~/ 0n",
+        "1.0d % 0d",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(
+            &format!("division_by_zero_{i}"),
+            &format!("print {division};"),
+        );
+        assert!(
+            out.contains("[Runtime error] Division by zero."),
+            "{}: {}",
+            division,
+            out
+        );
+        assert_eq!(code, 65, "{}", division);
+    }
+}
+
+#[test]
+fn power_with_too_many_digits_is_a_runtime_error() {
+    for (i, power) in ["0.00d ** 3000000000", "2n ** 100000000", "0.1d ** 200000"]
+        .iter()
+        .enumerate()
+    {
+        let (out, code) = run(&format!("large_power_{i}"), &format!("print {power};"));
+        assert!(
+            out.contains("[Runtime error] The result of the power has too many digits."),
+            "{}: {}",
+            power,
+            out
+        );
+        assert_eq!(code, 65, "{}", power);
+    }
+    let (out, code) = run(
+        "small_power",
+        "println(1n ** 4000000000); println(1.5d ** 3);",
+    );
+    assert_eq!(out, "1\n3.375\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn bitwise_operators_on_big_integers_are_a_runtime_error() {
+    for (i, operation) in ["5n & 1", "1 << 2n", "~5n", "4.0d | 1"].iter().enumerate() {
+        let (out, code) = run(
+            &format!("bitwise_exact_{i}"),
+            &format!("print {operation};"),
+        );
+        assert!(
+            out.contains(
+                "[Runtime error] Bitwise operators are not supported on big integers and decimals."
+            ),
+            "{}: {}",
+            operation,
+            out
+        );
+        assert_eq!(code, 65, "{}", operation);
+    }
+}
+
+#[test]
+fn invalid_number_literals_are_reported_at_their_column() {
+    for (i, (source, error)) in [
+        (
+            "print 0x;",
+            "Error at line 1-8: Expect hexadecimal digits after '0x'.",
+        ),
+        (
+            "print 1_;",
+            "Error at line 1-8: Expect a digit after '_' in a number.",
+        ),
+        (
+            "print 1_000_;",
+            "Error at line 1-12: Expect a digit after '_' in a number.",
+        ),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(&format!("invalid_literal_{i}"), source);
+        assert!(out.starts_with(error), "{}: {}", source, out);
+        assert_eq!(code, 65, "{}", source);
+    }
+}
+
+#[test]
+fn optional_chain_skips_the_rest_of_the_chain_on_nil() {
+    let (out, code) = run(
+        "optional_chain",
+        "var a = nil; var calls = 0;\n\
+         fun count() { calls = calls + 1; return 0; }\n\
+         print a?.b.c; print \" \"; print a?.b.c(); print \" \"; print a?.b(count())[count()].c;\n\
+         print \" \"; print calls; print \" \"; print a?.b.c ?? \"default\";\n\
+         class P { init() { this.b = nil; } m() { return \"m\"; } }\n\
+         print \" \"; print P()?.m();",
+    );
+    assert_eq!(out, "nil nil nil 0 default m");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn optional_chain_only_skips_on_a_nil_before_the_question_dot() {
+    let (out, code) = run(
+        "optional_chain_inner_nil",
+        "class P { init() { this.b = nil; } } print P()?.b.c;",
+    );
+    assert!(out.contains("[Runtime error] Only instances and classes have properties."));
+    assert_eq!(code, 65);
+    let (out, code) = run("optional_chain_group", "var a = nil; print (a?.b).c;");
+    assert!(out.contains("[Runtime error] Only instances and classes have properties."));
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn for_in_over_a_failing_iterable_is_a_runtime_error() {
+    let (out, code) = run("for_in_undefined", "for (x in undefinedvar) { print x; }");
+    assert_eq!(
+        out,
+        "Error at line 1-22: [Runtime error] Variable undefinedvar is not defined.\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn for_in_over_a_generator_that_fails_is_a_runtime_error() {
+    let (out, code) = run(
+        "for_in_failing_generator",
+        "fun* g() { yield 1; yield nope; }\nfor (x in g()) { println(x); }",
+    );
+    assert_eq!(
+        out,
+        "1\nError at line 1-30: [Runtime error] Variable nope is not defined.\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn errors_in_a_generator_body_are_reported_and_the_body_goes_on() {
+    let (out, code) = run(
+        "generator_errors",
+        "fun* g() {\n\
+           if (nope) { yield \"then\"; }\n\
+           yield 1;\n\
+           while (nope) { yield \"loop\"; }\n\
+           for (x in 5) { yield x; }\n\
+           yield 2;\n\
+         }\n\
+         for (x in g()) { println(x); }",
+    );
+    assert_eq!(
+        out,
+        "Error: [Runtime error] Expected a condition.\n1\n\
+         Error: [Runtime error] Expected a condition.\n\
+         Error at line 5-9: [Runtime error] Can only iterate over lists, maps, strings, generators and objects with an iterator() method.\n\
+         2\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn is_done_tells_a_yielded_nil_apart_from_the_end_of_a_generator() {
+    let (out, code) = run(
+        "generator_is_done",
+        "fun* g() { yield nil; yield 1; }\n\
+         var it = g();\n\
+         print it.isDone; print \" \"; print it.next(); print \" \"; print it.isDone;\n\
+         print \" \"; print it.next(); print \" \"; print it.next(); print \" \"; print it.isDone;",
+    );
+    assert_eq!(out, "false nil false 1 nil true");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn redeclaring_a_constant_global_is_a_runtime_error() {
+    for (i, declaration) in [
+        "var PI = 10;",
+        "const PI = 4;",
+        "fun PI() {}",
+        "class PI {}",
+        "var [e, PI] = [1, 2];",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(
+            &format!("redeclare_constant_{i}"),
+            &format!("const PI = 3;\n{declaration}\nprint PI;"),
+        );
+        assert_eq!(
+            &out[..out.find('\n').unwrap_or(out.len())],
+            format!(
+                "Error at line 2-{}: [Runtime error] Can't redeclare constant 'PI'.",
+                declaration.find("PI").unwrap() + 2
+            ),
+            "{}",
+            declaration
+        );
+        assert!(out.ends_with("\n3"), "{}: {}", declaration, out);
+        assert_eq!(code, 65, "{}", declaration);
+    }
+    let (out, code) = run(
+        "shadow_constant",
+        "const PI = 3; { var PI = 5; print PI; } print PI;",
+    );
+    assert_eq!(out, "53");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn spawned_fibers_take_turns_in_the_order_they_wake_up_in() {
+    let (out, code) = run(
+        "fiber_schedule",
+        "fun worker(name, delay) {\n\
+           fun run() { for (i in 0..3) { sleep(delay); println(name + \" \" + i); } }\n\
+           return run;\n\
+         }\n\
+         Fiber.spawn(worker(\"fast\", 10));\n\
+         Fiber.spawn(worker(\"slow\", 25));\n\
+         fun pinger() { for (k in 0..2) { println(\"ping \" + k); Fiber.yield(); } }\n\
+         Fiber.spawn(pinger);\n\
+         println(\"main start\");\n\
+         sleep(20);\n\
+         println(\"main woke at 20\");",
+    );
+    assert_eq!(
+        out,
+        "main start\nping 0\nping 1\nfast 0\nfast 1\nmain woke at 20\n\
+         slow 0\nfast 2\nslow 1\nslow 2\n"
+    );
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn resume_gives_the_error_of_a_failing_fiber_and_ends_it() {
+    let (out, code) = run(
+        "fiber_error",
+        "fun bad() { Fiber.yield(1); return len(5); }\n\
+         var b = Fiber(bad);\n\
+         println(b.resume());\n\
+         b.resume();\n\
+         println(b.isDone);\n\
+         b.resume();",
+    );
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.first(), Some(&"1"));
+    assert!(lines.contains(
+        &"Error at line 1-41: [Runtime error] Can only get the length of a list, map, range or string."
+    ));
+    assert_eq!(
+        lines[lines.len() - 2..],
+        [
+            "true",
+            "Error at line 6-10: [Runtime error] Can't resume a finished fiber."
+        ]
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn a_failing_spawned_fiber_does_not_stop_the_others() {
+    let (out, code) = run(
+        "fiber_error_spawned",
+        "fun bad() { return len(5); }\n\
+         fun good() { sleep(1); println(\"good\"); }\n\
+         Fiber.spawn(bad);\n\
+         Fiber.spawn(good);",
+    );
+    assert!(out.ends_with("good\n"), "{}", out);
+    assert!(out.contains("[Runtime error] Can only get the length"));
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn a_failed_assertion_shows_its_condition_as_it_was_written() {
+    let (out, code) = run(
+        "assert_text",
+        "var s = \"ab\";\n\
+         assert s == \"a b\";\n\
+         assert [1,2][0]<=0;\n\
+         assert s == \"x\" or\n  1 >= 2;",
+    );
+    assert_eq!(
+        out,
+        "Error at line 2-6: [Runtime error] Assertion 's == \"a b\"' failed.\n\
+         Error at line 3-6: [Runtime error] Assertion '[1,2][0]<=0' failed.\n\
+         Error at line 4-6: [Runtime error] Assertion 's == \"x\" or\n  1 >= 2' failed.\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn deep_recursion_in_a_fiber_is_a_runtime_error() {
+    let (out, code) = run(
+        "fiber_stack_overflow",
+        "fun down(n) { if (n == 0) return 0; return down(n - 1) + 1; }\n\
+         fun shallow() { return down(40); }\n\
+         fun deep() { return down(100000); }\n\
+         println(Fiber(shallow).resume());\n\
+         var f = Fiber(deep);\n\
+         f.resume();\n\
+         println(f.isDone);",
+    );
+    assert!(out.starts_with("40\n"), "{}", out);
+    assert!(out.contains("[Runtime error] Stack overflow."), "{}", out);
+    assert!(out.ends_with("true\n"), "{}", out);
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn making_too_many_fibers_is_a_runtime_error() {
+    let (out, code) = run(
+        "too_many_fibers",
+        "fun body() { Fiber.yield(1); }\n\
+         var fibers = {};\n\
+         for (i in 0..30001) { fibers[\"${i}\"] = Fiber(body); }\n\
+         println(len(fibers));\n\
+         fibers = {};\n\
+         println(Fiber(body).resume());",
+    );
+    assert_eq!(
+        out,
+        "Error at line 3-49: [Runtime error] Can't have more than 30000 fibers at once.\n30000\n1\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn a_generator_paused_in_a_fiber_is_still_running() {
+    let (out, code) = run(
+        "generator_in_fiber",
+        "fun* g() { Fiber.yield(\"paused\"); yield 1; }\n\
+         var gen = g();\n\
+         fun run() { println(gen.next()); }\n\
+         var f = Fiber(run);\n\
+         println(f.resume());\n\
+         gen.next();\n\
+         f.resume();",
+    );
+    assert_eq!(
+        out,
+        "paused\n\
+         Error at line 6-10: [Runtime error] Can't resume a generator that is already running.\n1\n"
+    );
+    assert_eq!(code, 65);
+}
+
+#[test]
+fn private_static_members_are_a_resolve_error() {
+    for (i, (member, column)) in [("static #x = 1;", 19), ("static #s() {}", 19)]
+        .iter()
+        .enumerate()
+    {
+        let (out, code) = run(
+            &format!("private_static_{i}"),
+            &format!("class A {{ {member} }}"),
+        );
+        assert_eq!(
+            out,
+            format!(
+                "Error at line 1-{}: [Resolve error] Static member '{}' can't be private.\n",
+                column,
+                &member[7..9]
+            ),
+            "{}",
+            member
+        );
+        assert_eq!(code, 65, "{}", member);
+    }
+}
+
+#[test]
+fn reflection_does_not_show_private_fields() {
+    let (out, code) = run(
+        "reflect_private",
+        "class B { init() { this.#s = 1; this.p = 2; } }\n\
+         var b = B();\n\
+         print hasField(b, \"#s\"); print \" \"; print hasField(b, \"p\"); print \" \"; print fields(b);",
+    );
+    assert_eq!(out, "false true [p]");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn an_iterator_with_is_done_can_give_nil_as_a_value() {
+    let (out, code) = run(
+        "iterator_is_done",
+        "class Seq {\n\
+           init(values) { this.values = values; this.i = 0; }\n\
+           iterator() { return this; }\n\
+           isDone { return this.i >= len(this.values); }\n\
+           next() { this.i = this.i + 1; return this.values[this.i - 1]; }\n\
+         }\n\
+         for (x in Seq([1, nil, 3])) { print x; print \" \"; }\n\
+         class Countdown {\n\
+           init() { this.n = 2; }\n\
+           iterator() { return this; }\n\
+           isDone() { return this.n < 0; }\n\
+           next() { this.n = this.n - 1; if (this.n == 0) return nil; return this.n + 1; }\n\
+         }\n\
+         for (x in Countdown()) { print x; print \" \"; }\n\
+         class Old {\n\
+           init() { this.n = 0; }\n\
+           iterator() { return this; }\n\
+           next() { this.n = this.n + 1; if (this.n > 2) return nil; return this.n; }\n\
+         }\n\
+         for (x in Old()) { print x; print \" \"; }",
+    );
+    assert_eq!(out, "1 nil 3 2 nil 0 1 2 ");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn an_empty_interpolation_is_a_parse_error_at_its_start() {
+    for (i, (source, column)) in [("print \"a${}b\";", 10), ("print \"${1}b${ }c\";", 14)]
+        .iter()
+        .enumerate()
+    {
+        let (out, code) = run(&format!("empty_interpolation_{i}"), source);
+        assert_eq!(
+            out,
+            format!(
+                "Error at line 1-{}: [Parse error] Expect expression.\n",
+                column
+            ),
+            "{}",
+            source
+        );
+        assert_eq!(code, 65, "{}", source);
+    }
+    let (out, code) = run("brace_in_interpolation", "print \"a${\"}\"}b\";");
+    assert_eq!(out, "a}b");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn two_minus_signs_without_a_space_are_a_decrement() {
+    let (out, code) = run("minus_minus", "print 1--1;");
+    assert_eq!(
+        out,
+        "Error at line 1-8: [Parse error] Invalid assignment target.\n"
+    );
+    assert_eq!(code, 65);
+    let (out, code) = run(
+        "minus_negative",
+        "var a = 3; print 1 - -1; print \" \"; print 1-(-1); print \" \"; print a - --a;",
+    );
+    assert_eq!(out, "2 2 1");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn compound_assignment_and_increments_evaluate_their_target_once() {
+    let (out, code) = run(
+        "compound_assignment",
+        "var calls = 0;\n\
+         class Box { init() { this.v = 10; } }\n\
+         var box = Box();\n\
+         fun get() { calls = calls + 1; return box; }\n\
+         var list = [1, 2, 3];\n\
+         fun at() { calls = calls + 1; return 1; }\n\
+         var x = 5;\n\
+         x += 2; x -= 1; x *= 3; x /= 2;\n\
+         print x; print \" \";\n\
+         get().v += 5; print box.v; print \" \";\n\
+         list[at()] *= 10; print list; print \" \";\n\
+         var i = 1;\n\
+         print i++; print i; print ++i; print i--; print --i; print \" \";\n\
+         print get().v++; print box.v; print \" \";\n\
+         print ++list[at()]; print list; print \" \";\n\
+         print calls;",
+    );
+    assert_eq!(out, "9 15 [1, 20, 3] 12331 1516 21[1, 21, 3] 4");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn default_parameters_are_evaluated_at_call_time_and_arguments_can_be_named() {
+    let (out, code) = run(
+        "default_parameters",
+        "fun greet(name, greeting = \"Hello\", punct = \"!\") { return greeting + \" \" + name + punct; }\n\
+         print greet(\"a\"); print \" \";\n\
+         print greet(\"a\", punct: \"?\"); print \" \";\n\
+         print greet(greeting: \"Hi\", name: \"b\"); print \" \";\n\
+         var n = 1;\n\
+         fun twice(x = n * 2) { return x; }\n\
+         n = 5;\n\
+         print twice(); print \" \";\n\
+         fun outer() { var local = \"closure\"; fun inner(v = local) { return v; } return inner; }\n\
+         print outer()();",
+    );
+    assert_eq!(out, "Hello a! Hello a? Hi b! 10 closure");
+    assert_eq!(code, 0);
+    for (i, (call, error)) in [
+        (
+            "fun g(a, b = 1) {} g();",
+            "Error at line 1-22: [Runtime error] Expected at least 1 arguments but got 0.",
+        ),
+        (
+            "fun g(a, b = 1) {} g(1, 2, 3);",
+            "Error at line 1-29: [Runtime error] Expected at most 2 arguments but got 3.",
+        ),
+        (
+            "fun g(a) {} g(1, a: 2);",
+            "Error at line 1-18: [Runtime error] Parameter 'a' was given more than once.",
+        ),
+        (
+            "fun g(a) {} g(b: 2);",
+            "Error at line 1-15: [Runtime error] No parameter named 'b'.",
+        ),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(&format!("bad_arguments_{i}"), call);
+        assert_eq!(out, format!("{}\n", error), "{}", call);
+        assert_eq!(code, 65, "{}", call);
+    }
+}
+
+#[test]
+fn destructuring_binds_lists_maps_and_instances_and_checks_their_shape() {
+    let (out, code) = run(
+        "destructuring",
+        "var [a, b] = [1, 2];\n\
+         [a, b] = [b, a];\n\
+         print a; print b; print \" \";\n\
+         var {x, y} = {\"x\": 3, \"y\": 4};\n\
+         print x + y; print \" \";\n\
+         class P { init() { this.w = 5; this.h = 6; } }\n\
+         var {w, h} = P();\n\
+         print w * h;",
+    );
+    assert_eq!(out, "21 7 30");
+    assert_eq!(code, 0);
+    for (i, (declaration, error)) in [
+        (
+            "var [a, b] = [1];",
+            "Error at line 1-5: [Runtime error] Expected 2 values to destructure but got 1.",
+        ),
+        (
+            "var [a, b] = [1, 2, 3];",
+            "Error at line 1-5: [Runtime error] Expected 2 values to destructure but got 3.",
+        ),
+        (
+            "var [a, b] = 5;",
+            "Error at line 1-5: [Runtime error] Only lists can be destructured with [ ].",
+        ),
+        (
+            "var {q} = {\"x\": 1};",
+            "Error at line 1-6: [Runtime error] Undefined key 'q'.",
+        ),
+        (
+            "var a; var b; [a, b] = [1];",
+            "Error at line 1-22: [Runtime error] Expected 2 values to destructure but got 1.",
+        ),
+        // Patterns don't nest, every element is a name.
+        (
+            "var [a, [b, c]] = [1, [2, 3]];",
+            "Error at line 1-9: [Parse error] Expect variable name.",
+        ),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(&format!("bad_destructuring_{i}"), declaration);
+        assert_eq!(out, format!("{}\n", error), "{}", declaration);
+        assert_eq!(code, 65, "{}", declaration);
+    }
+}
+
+#[test]
+fn assigning_to_a_constant_is_a_runtime_error() {
+    for (i, (source, column)) in [
+        ("const C = 1; C = 2; print C;", 14),
+        ("const C = 1; C += 1; print C;", 14),
+        ("const C = 1; C++; print C;", 14),
+        ("const C = 1; fun f() { C = 2; } f(); print C;", 24),
+        ("const [C, D] = [1, 2]; C = 3; print C;", 24),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let (out, code) = run(&format!("assign_constant_{i}"), source);
+        assert_eq!(
+            out,
+            format!(
+                "Error at line 1-{}: [Runtime error] Can't assign to constant 'C'.\n1",
+                column
+            ),
+            "{}",
+            source
+        );
+        assert_eq!(code, 65, "{}", source);
+    }
+}